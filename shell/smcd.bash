@@ -0,0 +1,14 @@
+# Spaceman cd-on-exit for bash. Add to ~/.bashrc:
+#   eval "$(sm shell-init bash)"
+# then run `smcd [options] [path]` and quit to cd where you were browsing.
+smcd() {
+    local choice
+    choice="$(mktemp)" || return
+    command sm --choose-dir "$choice" "$@"
+    local status=$?
+    if [ -s "$choice" ]; then
+        cd -- "$(cat -- "$choice")" || status=$?
+    fi
+    rm -f -- "$choice"
+    return $status
+}
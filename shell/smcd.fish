@@ -0,0 +1,15 @@
+# Spaceman cd-on-exit for fish. Add to ~/.config/fish/config.fish:
+#   sm shell-init fish | source
+# then run `smcd [options] [path]` and quit to cd where you were browsing.
+function smcd --description 'Browse with Spaceman and cd to the last directory'
+    set -l choice (mktemp)
+    or return
+    command sm --choose-dir $choice $argv
+    set -l exit_status $status
+    if test -s $choice
+        cd (cat $choice)
+        or set exit_status $status
+    end
+    rm -f $choice
+    return $exit_status
+end
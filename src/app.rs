@@ -1,9 +1,13 @@
 use anyhow::Result;
+use rayon::prelude::*;
 use std::path::PathBuf;
 
 use crate::{
     args::Args,
-    core::{FileEntry, Scanner},
+    core::{
+        summarize_artifacts, ArtifactRules, ArtifactSummary, DeletedTarget, ExcludeRules, ExtensionFilter,
+        ExtensionStats, FileEntry, Project, Scanner, TrashItem,
+    },
     ui::Terminal,
     error::SpacemanError,
 };
@@ -16,18 +20,38 @@ pub struct App {
     pub current_path: PathBuf,
     path_history: Vec<PathBuf>,
     pub initial_path: PathBuf,
+    pub top_files: Vec<FileEntry>,
+    pub extension_stats: Vec<ExtensionStats>,
+    /// What the extension picker offers: `extension_stats` and compound
+    /// extensions such as `tar.gz`, largest first
+    pub extension_choices: Vec<ExtensionStats>,
+    /// Directory the extension breakdown was taken of, while still current
+    extension_root: Option<PathBuf>,
+    pub artifacts: Vec<FileEntry>,
+    pub artifact_summary: Vec<ArtifactSummary>,
+    pub projects: Vec<Project>,
+    pub trash: Vec<TrashItem>,
+    /// Every path under `current_path`, indexed by the scan for searching
+    pub tree: Vec<FileEntry>,
+    /// Paths chosen in pick mode, `None` if the user quit without choosing
+    pub picked: Option<Vec<PathBuf>>,
 }
 
 impl App {
     pub fn new(args: &Args) -> Result<Self, SpacemanError> {
         args.validate()?;
 
+        let current_path = std::fs::canonicalize(&args.path)
+            .map_err(|e| SpacemanError::InvalidPath(format!("Failed to resolve path: {}", e)))?;
         let scanner = Scanner::new(args.depth, args.all)
-            .with_extension_filter(args.ext.clone());
+            .with_extension_filter(ExtensionFilter::from_list(&args.ext, args.ignore_case))
+            .with_excludes(ExcludeRules::new(&current_path, &args.exclude, &args.exclude_regex)?)
+            .with_ignore_mode(args.ignore_files)
+            .with_ignore_root(&current_path)
+            .with_artifact_rules(ArtifactRules::with_extra(&args.artifact_rule))
+            .with_thresholds(args.thresholds());
         let terminal = Terminal::new(args)?;
         let entries = Vec::new();
-        let current_path = std::fs::canonicalize(&args.path)
-            .map_err(|e| SpacemanError::InvalidPath(format!("Failed to resolve path: {}", e)))?;
         let initial_path = current_path.clone();
 
         Ok(Self {
@@ -38,21 +62,39 @@ impl App {
             current_path,
             path_history: Vec::new(),
             initial_path,
+            top_files: Vec::new(),
+            extension_stats: Vec::new(),
+            extension_choices: Vec::new(),
+            extension_root: None,
+            artifacts: Vec::new(),
+            artifact_summary: Vec::new(),
+            projects: Vec::new(),
+            trash: Vec::new(),
+            tree: Vec::new(),
+            picked: None,
         })
     }
 
     pub fn run(&mut self, _path: &str) -> Result<(), SpacemanError> {
         self.scan_current_directory()?;
-        
+        if let Some(limit) = self.args.top {
+            self.scan_largest_files(limit);
+        }
+
         let mut terminal = std::mem::replace(&mut self.terminal, Terminal::new(&self.args)?);
         terminal.run(self)?;
+        self.picked = terminal.take_picked();
         self.terminal = terminal;
 
         Ok(())
     }
 
+    pub fn scanner(&self) -> &Scanner {
+        &self.scanner
+    }
+
     pub fn scan_current_directory(&mut self) -> Result<(), SpacemanError> {
-        self.entries = self.scanner.scan(
+        (self.entries, self.tree) = self.scanner.scan(
             self.current_path
                 .to_str()
                 .ok_or_else(|| SpacemanError::InvalidPath("Invalid path".to_string()))?,
@@ -63,6 +105,73 @@ impl App {
         Ok(())
     }
 
+    pub fn scan_largest_files(&mut self, limit: usize) {
+        self.top_files = self.scanner.largest_files(&self.current_path, limit);
+    }
+
+    /// Totals the subtree per extension, unless the last breakdown is of
+    /// the current directory and nothing has changed since.
+    pub fn scan_extension_breakdown(&mut self) {
+        if self.extension_root.as_ref() == Some(&self.current_path) {
+            return;
+        }
+        let (stats, compound) = self.scanner.extension_breakdown(&self.current_path);
+        let mut choices: Vec<ExtensionStats> = stats.iter().cloned().chain(compound).collect();
+        choices.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.extension.cmp(&b.extension)));
+        self.extension_stats = stats;
+        self.extension_choices = choices;
+        self.extension_root = Some(self.current_path.clone());
+    }
+
+    pub fn scan_artifacts(&mut self) {
+        self.artifacts = self.scanner.find_artifacts(&self.current_path);
+        self.artifact_summary = summarize_artifacts(&self.artifacts);
+    }
+
+    pub fn scan_projects(&mut self) {
+        self.projects = self.scanner.find_projects(&self.current_path);
+    }
+
+    pub fn scan_trash(&mut self) {
+        let mut trash = crate::core::list_trash();
+        trash.par_iter_mut().for_each(|item| item.size = self.scanner.measure(&item.path));
+        self.trash = trash;
+    }
+
+    /// Rescans after something outside the app may have changed the disk,
+    /// which also rebuilds the search index.
+    pub fn refresh(&mut self) -> Result<(), SpacemanError> {
+        self.extension_root = None;
+        self.scan_current_directory()
+    }
+
+    /// Updates the listings after a deletion without rescanning: removed
+    /// paths drop out and every listed ancestor shrinks by the freed bytes.
+    pub fn forget_deleted(&mut self, targets: &[DeletedTarget]) {
+        for list in [&mut self.entries, &mut self.top_files, &mut self.artifacts, &mut self.tree] {
+            for target in targets {
+                if target.removed {
+                    list.retain(|e| !e.path.starts_with(&target.path));
+                }
+                for entry in list.iter_mut() {
+                    if target.path.starts_with(&entry.path) {
+                        entry.size = entry.size.saturating_sub(target.freed);
+                    }
+                }
+            }
+        }
+        self.artifact_summary = summarize_artifacts(&self.artifacts);
+        self.extension_root = None;
+    }
+
+    /// Restricts the listing to matching files and the directories
+    /// containing them, sized by the matching bytes only. `None` restores the
+    /// full listing.
+    pub fn set_extension_filter(&mut self, filter: Option<ExtensionFilter>) -> Result<(), SpacemanError> {
+        self.scanner.set_extension_filter(filter);
+        self.scan_current_directory()
+    }
+
     pub fn navigate_to(&mut self, path: PathBuf) -> Result<(), SpacemanError> {
         let canonical_path = std::fs::canonicalize(&path)
             .map_err(|e| SpacemanError::InvalidPath(format!("Failed to resolve path: {}", e)))?;
@@ -129,9 +238,9 @@ impl App {
             }
             "size" => {
                 if self.args.order == "asc" {
-                    self.entries.sort_by(|a, b| a.size.cmp(&b.size));
+                    self.entries.sort_by_key(|e| e.size);
                 } else {
-                    self.entries.sort_by(|a, b| b.size.cmp(&a.size));
+                    self.entries.sort_by_key(|e| std::cmp::Reverse(e.size));
                 }
             }
             "name" => {
@@ -143,9 +252,9 @@ impl App {
             }
             "modified" => {
                 if self.args.order == "asc" {
-                    self.entries.sort_by(|a, b| a.modified.cmp(&b.modified));
+                    self.entries.sort_by_key(|e| e.modified);
                 } else {
-                    self.entries.sort_by(|a, b| b.modified.cmp(&a.modified));
+                    self.entries.sort_by_key(|e| std::cmp::Reverse(e.modified));
                 }
             }
             _ => {
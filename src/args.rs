@@ -1,12 +1,18 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
+use crate::core::{ArtifactRule, CheckRule, IgnoreMode, Query, Thresholds};
 use crate::error::SpacemanError;
+use crate::ui::{Keymap, Theme};
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about = "A terminal-based file system explorer", long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Path to scan
     #[arg(default_value = ".")]
     pub path: String,
@@ -35,9 +41,110 @@ pub struct Args {
     #[arg(short = 'm', long)]
     pub no_modified: bool,
 
-    /// Filter by file extension
+    /// Filter by file extension, comma-separated or repeated (e.g. jpg,png,tar.gz)
+    #[arg(short, long, value_delimiter = ',')]
+    pub ext: Vec<String>,
+
+    /// Match file extensions case-insensitively
     #[arg(short, long)]
-    pub ext: Option<String>,
+    pub ignore_case: bool,
+
+    /// Skip paths matching a glob; matched on the name, or if it contains '/' on the path below the scanned directory (the full path if it starts with '/')
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Skip paths whose full path matches a regular expression
+    #[arg(long, value_name = "REGEX")]
+    pub exclude_regex: Vec<String>,
+
+    /// Honour .gitignore, .ignore and .spacemanignore files (hide, group)
+    #[arg(long, value_name = "MODE", value_parser = validate_ignore_mode)]
+    pub ignore_files: Option<IgnoreMode>,
+
+    /// Extra build artifact rule as tool:dir[:marker], e.g. bazel:bazel-out:WORKSPACE
+    #[arg(long, value_name = "RULE", value_parser = validate_artifact_rule)]
+    pub artifact_rule: Vec<ArtifactRule>,
+
+    /// Hide files and directories smaller than SIZE (e.g. 100M); they still count toward totals
+    #[arg(long, value_name = "SIZE", value_parser = validate_size)]
+    pub min_size: Option<u64>,
+
+    /// Hide files and directories larger than SIZE
+    #[arg(long, value_name = "SIZE", value_parser = validate_size)]
+    pub max_size: Option<u64>,
+
+    /// Hide files modified more recently than AGE ago (e.g. 30d, 12h, 2w)
+    #[arg(long, value_name = "AGE", value_parser = validate_age)]
+    pub older_than: Option<Duration>,
+
+    /// Hide files last modified longer than AGE ago
+    #[arg(long, value_name = "AGE", value_parser = validate_age)]
+    pub newer_than: Option<Duration>,
+
+    /// Only list entries matching an expression, e.g. "size > 100M and mtime < -90d and ext in (log, gz)"
+    #[arg(long = "where", value_name = "EXPR", value_parser = validate_query)]
+    pub query: Option<Query>,
+
+    /// Start in the largest-files view, listing the N largest files in the subtree
+    #[arg(long, value_name = "N", value_parser = validate_top)]
+    pub top: Option<usize>,
+
+    /// Start with the preview pane open
+    #[arg(long)]
+    pub preview: bool,
+
+    /// On quit, write the directory being browsed to FILE (for cd-on-exit)
+    #[arg(long, value_name = "FILE")]
+    pub choose_dir: Option<PathBuf>,
+
+    /// On quit, print the directory being browsed to stdout
+    #[arg(long)]
+    pub print_cwd: bool,
+
+    /// Pick entries interactively and print their paths to stdout
+    #[arg(long)]
+    pub pick: bool,
+
+    /// Separate picked paths with NUL instead of newline
+    #[arg(short = '0', long = "null", requires = "pick")]
+    pub null: bool,
+
+    /// Read defaults from FILE instead of ~/.config/spaceman/config.toml
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Colours, from the configuration file
+    #[arg(skip)]
+    pub theme: Theme,
+
+    /// Key bindings, from the configuration file
+    #[arg(skip)]
+    pub keymap: Keymap,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Report the size and age of known cache locations under $HOME
+    Caches,
+    /// Check size budgets and exit non-zero if any is exceeded (for CI)
+    Check {
+        /// Directory to scan; relative rule paths start here
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// File with one rule per line [default: PATH/.spaceman-check]
+        #[arg(long, value_name = "FILE")]
+        rules: Option<PathBuf>,
+
+        /// Rule such as "path: ./dist, max: 50M" or "pattern: **/*.log, max_total: 1G" (repeatable)
+        #[arg(long, value_name = "RULE", value_parser = validate_check_rule)]
+        rule: Vec<CheckRule>,
+    },
+    /// Print the smcd cd-on-exit function for a shell (bash, zsh, fish)
+    ShellInit {
+        #[arg(value_parser = validate_shell)]
+        shell: String,
+    },
 }
 
 fn validate_sort_order(s: &str) -> Result<String, String> {
@@ -54,7 +161,58 @@ fn validate_sort_direction(s: &str) -> Result<String, String> {
     }
 }
 
+fn validate_ignore_mode(s: &str) -> Result<IgnoreMode, String> {
+    match s {
+        "hide" => Ok(IgnoreMode::Hide),
+        "group" => Ok(IgnoreMode::Group),
+        _ => Err("Ignore mode must be one of: hide, group".to_string()),
+    }
+}
+
+fn validate_artifact_rule(s: &str) -> Result<ArtifactRule, String> {
+    s.parse()
+}
+
+fn validate_size(s: &str) -> Result<u64, String> {
+    crate::utils::parse_size(s)
+}
+
+fn validate_age(s: &str) -> Result<Duration, String> {
+    crate::utils::parse_age(s)
+}
+
+fn validate_check_rule(s: &str) -> Result<CheckRule, String> {
+    s.parse()
+}
+
+fn validate_query(s: &str) -> Result<Query, String> {
+    s.parse()
+}
+
+fn validate_shell(s: &str) -> Result<String, String> {
+    match s {
+        "bash" | "zsh" | "fish" => Ok(s.to_string()),
+        _ => Err("Shell must be one of: bash, zsh, fish".to_string()),
+    }
+}
+
+fn validate_top(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err("Top must be a number greater than 0".to_string()),
+    }
+}
+
 impl Args {
+    pub fn thresholds(&self) -> Thresholds {
+        Thresholds {
+            min_size: self.min_size,
+            max_size: self.max_size,
+            older_than: self.older_than,
+            newer_than: self.newer_than,
+        }
+    }
+
     pub fn validate(&self) -> Result<(), SpacemanError> {
         let path = PathBuf::from(&self.path);
         if !path.exists() {
@@ -70,6 +228,22 @@ impl Args {
             ));
         }
 
+        if let (Some(min), Some(max)) = (self.min_size, self.max_size)
+            && min > max
+        {
+            return Err(SpacemanError::InvalidThreshold(
+                "--min-size must not be larger than --max-size".to_string(),
+            ));
+        }
+
+        if let (Some(older), Some(newer)) = (self.older_than, self.newer_than)
+            && older > newer
+        {
+            return Err(SpacemanError::InvalidThreshold(
+                "--older-than must not be longer than --newer-than".to_string(),
+            ));
+        }
+
         if !["default", "size", "name", "modified"].contains(&self.sort.as_str()) {
             return Err(SpacemanError::InvalidSortOrder(format!(
                 "Invalid sort order: {}. Must be one of: default, size, name, modified",
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::{
+    args::Args,
+    core::{known_caches, ExcludeRules, Scanner},
+    error::SpacemanError,
+    utils::{format_duration, format_size},
+};
+
+/// Prints the size and age of every known cache location, largest first.
+pub fn run(args: &Args) -> Result<(), SpacemanError> {
+    let excludes = ExcludeRules::new(Path::new("/"), &args.exclude, &args.exclude_regex)?;
+
+    let mut caches: Vec<_> = known_caches()
+        .into_par_iter()
+        .filter_map(|cache| {
+            // Relative path globs are taken below each cache, e.g. `v8/**`
+            let scanner = Scanner::new(0, true).with_excludes(excludes.clone().with_root(&cache.path));
+            let (size, modified) = scanner.measure_with_newest(&cache.path)?;
+            Some((cache, size, modified))
+        })
+        .collect();
+    caches.sort_by_key(|(_, size, _)| std::cmp::Reverse(*size));
+
+    if caches.is_empty() {
+        println!("No known cache locations found");
+        return Ok(());
+    }
+
+    // Each cache is reported as a whole, so age bounds apply to it like a file
+    let thresholds = args.thresholds();
+    let found = caches.len();
+    caches.retain(|(_, size, modified)| thresholds.admits(*size, *modified, false));
+    let hidden = found - caches.len();
+    let total: u64 = caches.iter().map(|(_, size, _)| size).sum();
+
+    let name_width = caches.iter().map(|(c, _, _)| c.name.len()).max().unwrap_or(0).max(5);
+    println!("{:<name_width$}  {:>10}  {:>9}  Path", "Cache", "Size", "Modified");
+    for (cache, size, modified) in &caches {
+        let age = modified
+            .elapsed()
+            .map(|d| format!("{} ago", format_duration(d)))
+            .unwrap_or_else(|_| "-".to_string());
+        println!(
+            "{:<name_width$}  {:>10}  {:>9}  {}",
+            cache.name,
+            format_size(*size),
+            age,
+            cache.path.display()
+        );
+    }
+
+    if hidden > 0 {
+        println!(
+            "{:<name_width$}  {:>10}  ({} cache(s) outside the size or age limits not shown or counted)",
+            "Total",
+            format_size(total),
+            hidden
+        );
+    } else {
+        println!("{:<name_width$}  {:>10}", "Total", format_size(total));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use crate::{
+    args::Args,
+    core::{CheckRule, ExcludeRules, Scanner},
+    error::SpacemanError,
+};
+
+/// Rules read from the scanned directory when none are given.
+pub const DEFAULT_RULES_FILE: &str = ".spaceman-check";
+
+/// Scans `path` once, checks every rule against it and prints a report.
+/// Returns whether all rules passed.
+pub fn run(args: &Args, path: &Path, rules_file: Option<&Path>, rules: &[CheckRule]) -> Result<bool, SpacemanError> {
+    let root = std::fs::canonicalize(path)
+        .map_err(|e| SpacemanError::InvalidPath(format!("{}: {}", path.display(), e)))?;
+
+    let mut rules = rules.to_vec();
+    match rules_file {
+        Some(file) => rules.extend(read_rules(file)?),
+        None if rules.is_empty() => rules.extend(read_rules(&root.join(DEFAULT_RULES_FILE))?),
+        None => {}
+    }
+    if rules.is_empty() {
+        return Err(SpacemanError::InvalidRule("No rules to check".to_string()));
+    }
+
+    // Hidden files take up space in build contexts like anything else
+    let scanner = Scanner::new(1, true)
+        .with_excludes(ExcludeRules::new(&root, &args.exclude, &args.exclude_regex)?)
+        .with_ignore_mode(args.ignore_files)
+        .with_ignore_root(&root);
+    let tree = scanner.index_tree(&root);
+
+    let outcomes: Vec<_> = rules.iter().map(|rule| rule.evaluate(&root, &tree)).collect();
+    for outcome in &outcomes {
+        println!("{}  {}", if outcome.passed() { "PASS" } else { "FAIL" }, outcome.rule);
+        println!("      {}", outcome.summary);
+        for violation in &outcome.violations {
+            println!("      {}", violation);
+        }
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    if failed == 0 {
+        println!("All {} rule(s) passed", outcomes.len());
+    } else {
+        println!("{} of {} rule(s) failed", failed, outcomes.len());
+    }
+    Ok(failed == 0)
+}
+
+/// One rule per line; blank lines and lines starting with `#` are skipped.
+fn read_rules(file: &Path) -> Result<Vec<CheckRule>, SpacemanError> {
+    let text = std::fs::read_to_string(file)
+        .map_err(|e| SpacemanError::InvalidRule(format!("Cannot read {}: {}", file.display(), e)))?;
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            line.parse()
+                .map_err(|e| SpacemanError::InvalidRule(format!("{}:{}: {}", file.display(), number, e)))
+        })
+        .collect()
+}
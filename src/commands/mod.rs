@@ -0,0 +1,3 @@
+pub mod caches;
+pub mod check;
+pub mod shell_init;
@@ -0,0 +1,10 @@
+/// Prints the `smcd` function for `shell`, which runs Spaceman and then
+/// changes to the directory that was being browsed on quit.
+pub fn run(shell: &str) {
+    let script = match shell {
+        "bash" => include_str!("../../shell/smcd.bash"),
+        "zsh" => include_str!("../../shell/smcd.zsh"),
+        _ => include_str!("../../shell/smcd.fish"),
+    };
+    print!("{}", script);
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::{parser::ValueSource, ArgMatches};
+use serde::Deserialize;
+
+use crate::args::Args;
+use crate::error::SpacemanError;
+use crate::ui::{parse_sequence, Action, Keymap, Theme, KEYMAP_NAMES};
+
+/// Defaults read from `~/.config/spaceman/config.toml`, or the file given
+/// with `--config`. Anything also given on the command line is overridden.
+///
+/// ```toml
+/// sort = "size"
+/// order = "desc"
+/// depth = 2
+/// all = true
+/// columns = ["modified"]
+/// exclude = [".git", "node_modules"]
+/// theme = "light"
+/// keymap = "vim"
+///
+/// [colors]
+/// primary = "cyan"
+///
+/// [keys]
+/// quit = ["q", "ctrl-c"]
+/// first = "g g"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where the settings came from, for error messages
+    #[serde(skip)]
+    path: PathBuf,
+    sort: Option<String>,
+    order: Option<String>,
+    depth: Option<usize>,
+    /// Show hidden files
+    all: Option<bool>,
+    /// Optional columns to show, out of `permissions` and `modified`
+    columns: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    exclude_regex: Option<Vec<String>>,
+    theme: Option<String>,
+    /// Colour overrides per theme role
+    colors: BTreeMap<String, String>,
+    /// Built-in keymap to start from: default, vim or emacs
+    keymap: Option<String>,
+    /// Key bindings per action, replacing those of the keymap
+    keys: BTreeMap<String, Bindings>,
+}
+
+/// One key sequence or a list of them.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Bindings {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Config {
+    /// Reads `path`, or the default location if it is `None`. A missing
+    /// default file is fine, a missing explicit one is an error.
+    pub fn load(path: Option<&Path>) -> Result<Self, SpacemanError> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(SpacemanError::InvalidConfig(format!("{}: {}", path.display(), e))),
+        };
+        let mut config: Self = toml::from_str(&text).map_err(|e| {
+            let line = e.span().map_or(1, |span| text[..span.start].matches('\n').count() + 1);
+            SpacemanError::InvalidConfig(format!("{}:{}: {}", path.display(), line, e.message()))
+        })?;
+        config.path = path;
+        Ok(config)
+    }
+
+    /// Fills in every setting of `args` that was not given on the command
+    /// line.
+    pub fn apply(self, args: &mut Args, matches: &ArgMatches) -> Result<(), SpacemanError> {
+        let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+        let path = self.path.clone();
+        let invalid = |message: String| SpacemanError::InvalidConfig(format!("{}: {}", path.display(), message));
+
+        if let Some(sort) = self.sort
+            && unset("sort")
+        {
+            args.sort = sort;
+        }
+        if let Some(order) = self.order
+            && unset("order")
+        {
+            args.order = order;
+        }
+        if let Some(depth) = self.depth
+            && unset("depth")
+        {
+            args.depth = depth;
+        }
+        if let Some(all) = self.all
+            && unset("all")
+        {
+            args.all = all;
+        }
+        if let Some(columns) = self.columns {
+            if let Some(unknown) = columns.iter().find(|c| !["permissions", "modified"].contains(&c.as_str())) {
+                return Err(invalid(format!("Unknown column '{}', expected permissions or modified", unknown)));
+            }
+            // The flags can only hide columns, so they win when given
+            if unset("no_permissions") {
+                args.no_permissions = !columns.iter().any(|c| c == "permissions");
+            }
+            if unset("no_modified") {
+                args.no_modified = !columns.iter().any(|c| c == "modified");
+            }
+        }
+        if let Some(exclude) = self.exclude
+            && unset("exclude")
+        {
+            args.exclude = exclude;
+        }
+        if let Some(exclude_regex) = self.exclude_regex
+            && unset("exclude_regex")
+        {
+            args.exclude_regex = exclude_regex;
+        }
+
+        if let Some(name) = &self.theme {
+            args.theme = Theme::named(name)
+                .ok_or_else(|| invalid(format!("Unknown theme '{}', expected one of {}", name, Theme::NAMES)))?;
+        }
+        for (role, color) in &self.colors {
+            args.theme.set(role, color).map_err(invalid)?;
+        }
+        if let Some(name) = &self.keymap {
+            args.keymap = Keymap::preset(name)
+                .ok_or_else(|| invalid(format!("Unknown keymap '{}', expected one of {}", name, KEYMAP_NAMES)))?;
+        }
+        for (name, bindings) in self.keys {
+            let action = Action::from_name(&name)
+                .ok_or_else(|| invalid(format!("Unknown action '{}', press ? in the browser to list them", name)))?;
+            let sequences = match bindings {
+                Bindings::One(keys) => vec![keys],
+                Bindings::Many(keys) => keys,
+            };
+            let sequences = sequences
+                .iter()
+                .map(|keys| parse_sequence(keys).map_err(|e| invalid(format!("{}: {}", name, e))))
+                .collect::<Result<Vec<_>, _>>()?;
+            args.keymap.bind(action, sequences);
+        }
+        Ok(())
+    }
+}
+
+/// `$XDG_CONFIG_HOME/spaceman/config.toml`, falling back to `~/.config`.
+fn default_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("spaceman").join("config.toml"))
+}
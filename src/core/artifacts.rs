@@ -0,0 +1,130 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::core::FileEntry;
+
+/// Built-in regenerable directories as `(tool, directory name, marker)`. When
+/// a marker is given, the directory only counts if a sibling with that name
+/// exists, so an unrelated `build/` is left alone.
+const DEFAULT_RULES: &[(&str, &str, Option<&str>)] = &[
+    ("cargo", "target", Some("Cargo.toml")),
+    ("maven", "target", Some("pom.xml")),
+    ("npm", "node_modules", None),
+    ("next.js", ".next", None),
+    ("nuxt", ".nuxt", None),
+    ("parcel", ".parcel-cache", None),
+    ("python", "__pycache__", None),
+    ("python", ".venv", None),
+    ("python", ".tox", None),
+    ("python", ".pytest_cache", None),
+    ("python", ".mypy_cache", None),
+    ("gradle", ".gradle", None),
+    ("gradle", "build", Some("build.gradle")),
+    ("gradle", "build", Some("build.gradle.kts")),
+    ("cmake", "build", Some("CMakeLists.txt")),
+    ("swift", ".build", Some("Package.swift")),
+    ("dart", ".dart_tool", None),
+    ("elixir", "_build", Some("mix.exs")),
+    ("haskell", ".stack-work", None),
+    ("zig", ".zig-cache", None),
+    ("zig", "zig-out", Some("build.zig")),
+    ("terraform", ".terraform", None),
+];
+
+/// One entry of the artifact table, written as `tool:dir[:marker]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactRule {
+    pub tool: String,
+    pub dir_name: String,
+    pub marker: Option<String>,
+}
+
+impl FromStr for ArtifactRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(tool), Some(dir_name), marker, None) if !tool.is_empty() && !dir_name.is_empty() => {
+                Ok(Self {
+                    tool: tool.to_string(),
+                    dir_name: dir_name.to_string(),
+                    marker: marker.filter(|m| !m.is_empty()).map(str::to_string),
+                })
+            }
+            _ => Err(format!("Artifact rule must look like tool:dir[:marker], got: {}", s)),
+        }
+    }
+}
+
+/// The table of regenerable directories the scanner tags.
+#[derive(Debug, Clone)]
+pub struct ArtifactRules {
+    rules: Vec<ArtifactRule>,
+}
+
+impl ArtifactRules {
+    /// The built-in table with `extra` rules checked first.
+    pub fn with_extra(extra: &[ArtifactRule]) -> Self {
+        let mut rules = extra.to_vec();
+        rules.extend(DEFAULT_RULES.iter().map(|(tool, dir_name, marker)| ArtifactRule {
+            tool: tool.to_string(),
+            dir_name: dir_name.to_string(),
+            marker: marker.map(str::to_string),
+        }));
+        Self { rules }
+    }
+
+    /// Returns the tool that regenerates the directory at `path`, if any.
+    pub fn detect(&self, path: &Path) -> Option<&str> {
+        let name = path.file_name()?.to_str()?;
+        let parent = path.parent()?;
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.dir_name == name
+                    && rule
+                        .marker
+                        .as_ref()
+                        .is_none_or(|marker| parent.join(marker).exists())
+            })
+            .map(|rule| rule.tool.as_str())
+    }
+}
+
+impl Default for ArtifactRules {
+    fn default() -> Self {
+        Self::with_extra(&[])
+    }
+}
+
+/// Reclaimable bytes for one tool across a subtree.
+#[derive(Debug, Clone)]
+pub struct ArtifactSummary {
+    pub tool: String,
+    pub size: u64,
+    pub dirs: usize,
+}
+
+/// Totals tagged directories by tool, largest first.
+pub fn summarize_artifacts(artifacts: &[FileEntry]) -> Vec<ArtifactSummary> {
+    let mut summary: Vec<ArtifactSummary> = Vec::new();
+    for entry in artifacts {
+        let Some(tool) = &entry.artifact else {
+            continue;
+        };
+        match summary.iter_mut().find(|s| s.tool == *tool) {
+            Some(s) => {
+                s.size += entry.size;
+                s.dirs += 1;
+            }
+            None => summary.push(ArtifactSummary {
+                tool: tool.clone(),
+                size: entry.size,
+                dirs: 1,
+            }),
+        }
+    }
+    summary.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.tool.cmp(&b.tool)));
+    summary
+}
@@ -0,0 +1,68 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// A well-known cache directory that is safe to clear and will be refilled
+/// by the tool that owns it.
+#[derive(Debug, Clone)]
+pub struct CacheLocation {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Lists the known cache locations under `$HOME` and `XDG_CACHE_HOME` that
+/// exist on this machine, including every directory directly inside the XDG
+/// cache directory.
+pub fn known_caches() -> Vec<CacheLocation> {
+    let Some(home) = env_dir("HOME") else {
+        return Vec::new();
+    };
+    let cache_home = env_dir("XDG_CACHE_HOME").unwrap_or_else(|| home.join(".cache"));
+    let data_home = env_dir("XDG_DATA_HOME").unwrap_or_else(|| home.join(".local/share"));
+    let cargo_home = env_dir("CARGO_HOME").unwrap_or_else(|| home.join(".cargo"));
+    let go_mod_cache = env_dir("GOMODCACHE")
+        .or_else(|| env_dir("GOPATH").map(|p| p.join("pkg/mod")))
+        .unwrap_or_else(|| home.join("go/pkg/mod"));
+
+    let mut caches: Vec<CacheLocation> = [
+        ("cargo registry", cargo_home.join("registry")),
+        ("cargo git checkouts", cargo_home.join("git")),
+        ("pip", cache_home.join("pip")),
+        ("npm", home.join(".npm")),
+        ("yarn", cache_home.join("yarn")),
+        ("yarn berry", home.join(".yarn/berry/cache")),
+        ("pnpm store", data_home.join("pnpm/store")),
+        ("go mod cache", go_mod_cache),
+        ("trash", data_home.join("Trash")),
+    ]
+    .into_iter()
+    .map(|(name, path)| CacheLocation {
+        name: name.to_string(),
+        path,
+    })
+    .collect();
+
+    if let Ok(read_dir) = fs::read_dir(&cache_home) {
+        let mut others: Vec<CacheLocation> = read_dir
+            .flatten()
+            .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+            .map(|entry| entry.path())
+            .filter(|path| !caches.iter().any(|c| c.path == *path))
+            .map(|path| CacheLocation {
+                name: format!("cache/{}", path.file_name().unwrap_or_default().to_string_lossy()),
+                path,
+            })
+            .collect();
+        others.sort_by(|a, b| a.name.cmp(&b.name));
+        caches.extend(others);
+    }
+
+    caches.retain(|c| c.path.is_dir());
+    caches
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+}
@@ -0,0 +1,276 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+use crate::core::FileEntry;
+use crate::utils::{format_size, parse_size};
+
+/// Offending files listed per rule before the rest are only counted.
+const REPORTED_FILES: usize = 10;
+/// Keys a rule is written with.
+const KEYS: [&str; 4] = ["path", "pattern", "max", "max_total"];
+
+/// A size budget for `sm check`, written as comma-separated `key: value`
+/// pairs such as `path: ./dist, max: 50M` or `pattern: **/*.log, max_total: 1G`.
+/// Only a comma followed by a `key:` starts a new pair, so values such as
+/// `**/*.{log,gz}` may contain commas.
+///
+/// `path` alone budgets one file or directory. `pattern` is a glob over the
+/// files below `path` (or the scan root), relative to it; `max` then limits
+/// each matching file and `max_total` all of them together.
+#[derive(Debug, Clone)]
+pub struct CheckRule {
+    source: String,
+    path: Option<PathBuf>,
+    pattern: Option<GlobMatcher>,
+    max: Option<u64>,
+    max_total: Option<u64>,
+}
+
+/// How one rule fared against the scanned tree.
+#[derive(Debug, Clone)]
+pub struct CheckOutcome {
+    pub rule: String,
+    /// What was measured, e.g. "dist is 12.0 MB"
+    pub summary: String,
+    /// One line per broken limit, empty when the rule passed
+    pub violations: Vec<String>,
+}
+
+impl CheckOutcome {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl FromStr for CheckRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rule = Self {
+            source: s.trim().to_string(),
+            path: None,
+            pattern: None,
+            max: None,
+            max_total: None,
+        };
+        for pair in split_pairs(s) {
+            let Some((key, value)) = pair.split_once(':') else {
+                return Err(format!("Expected key: value, got: {}", pair.trim()));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "path" => rule.path = Some(PathBuf::from(value)),
+                "pattern" => {
+                    // `*` stays within one directory, `**` crosses them
+                    let glob = GlobBuilder::new(value)
+                        .literal_separator(true)
+                        .build()
+                        .map_err(|e| format!("Invalid pattern {}: {}", value, e.kind()))?;
+                    rule.pattern = Some(glob.compile_matcher());
+                }
+                "max" => rule.max = Some(parse_size(value)?),
+                "max_total" => rule.max_total = Some(parse_size(value)?),
+                other => return Err(format!("Unknown key '{}', expected one of {}", other, KEYS.join(", "))),
+            }
+        }
+
+        if rule.path.is_none() && rule.pattern.is_none() {
+            return Err(format!("Rule needs a path or a pattern: {}", rule.source));
+        }
+        if rule.max.is_none() && rule.max_total.is_none() {
+            return Err(format!("Rule needs a max or a max_total: {}", rule.source));
+        }
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for CheckRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl CheckRule {
+    /// Checks the rule against `tree`, the indexed entries below `root`.
+    pub fn evaluate(&self, root: &Path, tree: &[FileEntry]) -> CheckOutcome {
+        let base = match &self.path {
+            Some(path) => root.join(path).canonicalize().unwrap_or_else(|_| root.join(path)),
+            None => root.to_path_buf(),
+        };
+        let shown = self.path.as_ref().map_or_else(|| ".".to_string(), |p| p.display().to_string());
+        let mut outcome = CheckOutcome {
+            rule: self.source.clone(),
+            summary: String::new(),
+            violations: Vec::new(),
+        };
+
+        let Some(pattern) = &self.pattern else {
+            let Some(entry) = tree.iter().find(|e| e.path == base) else {
+                outcome.summary = format!("{} does not exist below the scanned directory", shown);
+                outcome.violations.push("Not found".to_string());
+                return outcome;
+            };
+            outcome.summary = format!("{} is {}", shown, format_size(entry.size));
+            for limit in [self.max, self.max_total].into_iter().flatten() {
+                if entry.size > limit {
+                    outcome.violations.push(exceeded("", entry.size, limit));
+                }
+            }
+            return outcome;
+        };
+
+        let matches: Vec<&FileEntry> = tree
+            .iter()
+            .filter(|e| !e.is_dir && e.path.starts_with(&base))
+            .filter(|e| pattern.is_match(e.path.strip_prefix(&base).unwrap_or(&e.path)))
+            .collect();
+        let total: u64 = matches.iter().map(|e| e.size).sum();
+        outcome.summary = format!("{} file(s), {} in total", matches.len(), format_size(total));
+
+        if let Some(max) = self.max {
+            let mut oversized: Vec<&&FileEntry> = matches.iter().filter(|e| e.size > max).collect();
+            oversized.sort_by_key(|e| std::cmp::Reverse(e.size));
+            for entry in oversized.iter().take(REPORTED_FILES) {
+                let relative = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+                outcome.violations.push(over(&relative.display().to_string(), entry.size, max));
+            }
+            if oversized.len() > REPORTED_FILES {
+                outcome
+                    .violations
+                    .push(format!("...and {} more file(s) over {}", oversized.len() - REPORTED_FILES, format_size(max)));
+            }
+        }
+        if let Some(max_total) = self.max_total
+            && total > max_total
+        {
+            outcome.violations.push(exceeded(" total", total, max_total));
+        }
+        outcome
+    }
+}
+
+/// Splits a rule at the commas that are followed by a `key:`, where a key
+/// is a word such as `max_total`. Misspelled keys still start a pair, so
+/// they are reported rather than read as part of the previous value.
+fn split_pairs(rule: &str) -> Vec<&str> {
+    let starts_pair = |rest: &str| {
+        rest.split_once(':').is_some_and(|(key, _)| {
+            let key = key.trim();
+            !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+    };
+    let mut pairs = Vec::new();
+    let mut start = 0;
+    for (i, _) in rule.match_indices(',') {
+        if starts_pair(&rule[i + 1..]) {
+            pairs.push(&rule[start..i]);
+            start = i + 1;
+        }
+    }
+    pairs.push(&rule[start..]);
+    pairs
+}
+
+fn exceeded(kind: &str, size: u64, limit: u64) -> String {
+    format!("Over the {}{} limit by {}", format_size(limit), kind, format_size(size - limit))
+}
+
+fn over(what: &str, size: u64, limit: u64) -> String {
+    format!("{} is {}, over the {} limit", what, format_size(size), format_size(limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn entry(path: &str, size: u64, is_dir: bool) -> FileEntry {
+        let path = PathBuf::from(path);
+        FileEntry {
+            name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            path,
+            size,
+            is_dir,
+            modified: SystemTime::now(),
+            permissions: String::new(),
+            uid: 0,
+            skipped: false,
+            ignored_bucket: false,
+            artifact: None,
+        }
+    }
+
+    fn tree() -> Vec<FileEntry> {
+        vec![
+            entry("/project", 70 * MB, true),
+            entry("/project/dist", 60 * MB, true),
+            entry("/project/dist/app.js", 40 * MB, false),
+            entry("/project/dist/vendor.js", 20 * MB, false),
+            entry("/project/logs", 10 * MB, true),
+            entry("/project/logs/a.log", 6 * MB, false),
+            entry("/project/logs/b.gz", 3 * MB, false),
+            entry("/project/logs/c.txt", MB, false),
+        ]
+    }
+
+    fn check(rule: &str) -> CheckOutcome {
+        rule.parse::<CheckRule>().unwrap().evaluate(Path::new("/project"), &tree())
+    }
+
+    #[test]
+    fn commas_inside_values_do_not_split_pairs() {
+        let rule: CheckRule = "pattern: **/*.{log,gz}, max_total: 1G".parse().unwrap();
+        assert_eq!(rule.max_total, Some(1024 * MB));
+        assert!(rule.pattern.unwrap().is_match("logs/b.gz"));
+        assert_eq!(split_pairs("path: a,b, max: 1M"), vec!["path: a,b", " max: 1M"]);
+    }
+
+    #[test]
+    fn rejects_incomplete_or_unknown_rules() {
+        let error = |rule: &str| rule.parse::<CheckRule>().unwrap_err();
+        assert!(error("max: 1M").starts_with("Rule needs a path or a pattern"));
+        assert!(error("path: dist").starts_with("Rule needs a max or a max_total"));
+        assert!(error("path: dist, limit: 1M").starts_with("Unknown key 'limit'"));
+        assert!(error("dist").starts_with("Expected key: value"));
+        assert!("path: dist, max: lots".parse::<CheckRule>().is_err());
+    }
+
+    #[test]
+    fn path_rules_budget_one_entry() {
+        let outcome = check("path: ./dist, max: 50M");
+        assert!(!outcome.passed());
+        assert_eq!(outcome.summary, "./dist is 60.0 MB");
+        assert_eq!(outcome.violations, vec!["Over the 50.0 MB limit by 10.0 MB"]);
+
+        assert!(check("path: ./dist, max: 100M").passed());
+        assert_eq!(check("path: missing, max: 1M").violations, vec!["Not found"]);
+    }
+
+    #[test]
+    fn pattern_rules_match_below_the_path() {
+        let outcome = check("pattern: **/*.{log,gz}, max_total: 100M");
+        assert!(outcome.passed());
+        assert_eq!(outcome.summary, "2 file(s), 9.0 MB in total");
+
+        // `*` stays within one directory
+        assert_eq!(check("pattern: *.js, max_total: 1G").summary, "0 file(s), 0.0 B in total");
+        assert_eq!(check("path: dist, pattern: *.js, max_total: 1G").summary, "2 file(s), 60.0 MB in total");
+    }
+
+    #[test]
+    fn max_limits_each_file_and_max_total_all_of_them() {
+        let outcome = check("path: dist, pattern: *.js, max: 30M, max_total: 50M");
+        assert_eq!(
+            outcome.violations,
+            vec![
+                "dist/app.js is 40.0 MB, over the 30.0 MB limit",
+                "Over the 50.0 MB total limit by 10.0 MB",
+            ]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use walkdir::WalkDir;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Outcome of deleting one requested path.
+#[derive(Debug, Clone)]
+pub struct DeletedTarget {
+    pub path: PathBuf,
+    /// Bytes actually freed, even if the path could only be partly removed
+    pub freed: u64,
+    pub removed: bool,
+}
+
+#[derive(Debug)]
+pub enum DeleteEvent {
+    Progress { freed: u64, files: usize },
+    Finished { targets: Vec<DeletedTarget>, errors: Vec<String> },
+}
+
+/// Deletes `paths` recursively on a background thread, reporting progress
+/// and the final result over the returned channel.
+pub fn spawn_delete(paths: Vec<PathBuf>) -> Receiver<DeleteEvent> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut progress = Progress::new(tx.clone());
+        let mut targets = Vec::with_capacity(paths.len());
+        let mut errors = Vec::new();
+
+        for path in paths {
+            let freed_before = progress.freed;
+            let removed = delete_path(&path, &mut progress, &mut errors);
+            targets.push(DeletedTarget {
+                freed: progress.freed - freed_before,
+                path,
+                removed,
+            });
+        }
+
+        let _ = tx.send(DeleteEvent::Finished { targets, errors });
+    });
+    rx
+}
+
+struct Progress {
+    tx: Sender<DeleteEvent>,
+    freed: u64,
+    files: usize,
+    last_sent: Instant,
+}
+
+impl Progress {
+    fn new(tx: Sender<DeleteEvent>) -> Self {
+        Self {
+            tx,
+            freed: 0,
+            files: 0,
+            last_sent: Instant::now(),
+        }
+    }
+
+    fn add(&mut self, bytes: u64) {
+        self.freed += bytes;
+        self.files += 1;
+        if self.last_sent.elapsed() >= PROGRESS_INTERVAL {
+            let _ = self.tx.send(DeleteEvent::Progress {
+                freed: self.freed,
+                files: self.files,
+            });
+            self.last_sent = Instant::now();
+        }
+    }
+}
+
+/// Removes `path` bottom-up so progress can be reported file by file.
+/// Returns whether the path itself is gone.
+fn delete_path(path: &Path, progress: &mut Progress, errors: &mut Vec<String>) -> bool {
+    let walker = WalkDir::new(path)
+        .follow_links(false)
+        .same_file_system(true)
+        .contents_first(true);
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(e) => {
+                errors.push(e.to_string());
+                continue;
+            }
+        };
+
+        let size = entry.metadata().map(|m| m.blocks() * 512).unwrap_or(0);
+        let result = if entry.file_type().is_dir() {
+            fs::remove_dir(entry.path())
+        } else {
+            fs::remove_file(entry.path())
+        };
+
+        match result {
+            Ok(()) => progress.add(size),
+            Err(e) => errors.push(format!("{}: {}", entry.path().display(), e)),
+        }
+    }
+
+    fs::symlink_metadata(path).is_err()
+}
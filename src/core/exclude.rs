@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::RegexSet;
+
+use crate::error::SpacemanError;
+
+/// Paths the scanner skips entirely, without a stat or recursion.
+///
+/// Globs without a `/` match against the file name (`node_modules`, `*.log`).
+/// Globs with one match against the full path if they start with `/`
+/// (`/mnt/nfs/**`), and otherwise against the path relative to `root`
+/// (`build/out`). Regexes are searched for anywhere in the full path.
+#[derive(Debug, Clone)]
+pub struct ExcludeRules {
+    root: PathBuf,
+    name_globs: GlobSet,
+    path_globs: GlobSet,
+    relative_globs: GlobSet,
+    regexes: RegexSet,
+}
+
+impl ExcludeRules {
+    pub fn new(root: &Path, globs: &[String], regexes: &[String]) -> Result<Self, SpacemanError> {
+        let mut name_globs = GlobSetBuilder::new();
+        let mut path_globs = GlobSetBuilder::new();
+        let mut relative_globs = GlobSetBuilder::new();
+        for pattern in globs {
+            let trimmed = pattern.trim_end_matches('/');
+            let relative = trimmed.strip_prefix("./").unwrap_or(trimmed);
+            let glob = Glob::new(relative)
+                .map_err(|e| SpacemanError::InvalidPattern(format!("{}: {}", pattern, e)))?;
+            if relative.starts_with('/') {
+                path_globs.add(glob);
+            } else if relative.contains('/') || relative != trimmed {
+                relative_globs.add(glob);
+            } else {
+                name_globs.add(glob);
+            }
+        }
+
+        let regexes = RegexSet::new(regexes)
+            .map_err(|e| SpacemanError::InvalidPattern(e.to_string()))?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            name_globs: name_globs
+                .build()
+                .map_err(|e| SpacemanError::InvalidPattern(e.to_string()))?,
+            path_globs: path_globs
+                .build()
+                .map_err(|e| SpacemanError::InvalidPattern(e.to_string()))?,
+            relative_globs: relative_globs
+                .build()
+                .map_err(|e| SpacemanError::InvalidPattern(e.to_string()))?,
+            regexes,
+        })
+    }
+
+    /// The same rules with relative path globs starting at `root` instead.
+    pub fn with_root(mut self, root: &Path) -> Self {
+        self.root = root.to_path_buf();
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name_globs.is_empty()
+            && self.path_globs.is_empty()
+            && self.relative_globs.is_empty()
+            && self.regexes.is_empty()
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        if let Some(name) = path.file_name()
+            && self.name_globs.is_match(name)
+        {
+            return true;
+        }
+
+        if let Ok(relative) = path.strip_prefix(&self.root)
+            && self.relative_globs.is_match(relative)
+        {
+            return true;
+        }
+
+        self.path_globs.is_match(path) || self.regexes.is_match(&path.to_string_lossy())
+    }
+}
+
+impl Default for ExcludeRules {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::new(),
+            name_globs: GlobSet::empty(),
+            path_globs: GlobSet::empty(),
+            relative_globs: GlobSet::empty(),
+            regexes: RegexSet::empty(),
+        }
+    }
+}
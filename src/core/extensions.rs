@@ -0,0 +1,92 @@
+use std::fmt;
+use std::path::Path;
+
+/// Total size and file count for one file extension across a subtree.
+#[derive(Debug, Clone)]
+pub struct ExtensionStats {
+    pub extension: String,
+    pub size: u64,
+    pub files: usize,
+}
+
+/// A set of file extensions to match, optionally ignoring case. Compound
+/// extensions such as `tar.gz` match on the end of the file name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionFilter {
+    extensions: Vec<String>,
+    ignore_case: bool,
+}
+
+impl ExtensionFilter {
+    /// Builds a filter from a list of extensions, each of which may itself be
+    /// comma-separated. Returns `None` when no extension is left.
+    pub fn from_list<S: AsRef<str>>(list: &[S], ignore_case: bool) -> Option<Self> {
+        let mut extensions: Vec<String> = list
+            .iter()
+            .flat_map(|item| item.as_ref().split(','))
+            .map(|ext| ext.trim().trim_start_matches('.'))
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| if ignore_case { ext.to_lowercase() } else { ext.to_string() })
+            .collect();
+        extensions.sort();
+        extensions.dedup();
+
+        if extensions.is_empty() {
+            None
+        } else {
+            Some(Self { extensions, ignore_case })
+        }
+    }
+
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    pub fn ignore_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            return false;
+        };
+        let name = if self.ignore_case { name.to_lowercase() } else { name.to_string() };
+
+        self.extensions.iter().any(|ext| {
+            // The name must have a stem, so `.gz` alone is not a `gz` file
+            name.len() > ext.len() + 1
+                && name.ends_with(ext.as_str())
+                && name.as_bytes()[name.len() - ext.len() - 1] == b'.'
+        })
+    }
+}
+
+impl fmt::Display for ExtensionFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extensions.join(","))?;
+        if self.ignore_case {
+            write!(f, " [any case]")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn extension_of(path: &Path) -> Option<&str> {
+    path.extension().and_then(|e| e.to_str())
+}
+
+/// The last two extensions of a file name, such as `tar.gz` or `min.js`,
+/// when the inner one is short enough to be an extension rather than part of
+/// the name.
+pub fn compound_extension_of(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+    let mut dots = name.rmatch_indices('.').map(|(i, _)| i);
+    let last = dots.next()?;
+    let inner = dots.next().filter(|&i| i > 0)?;
+    let middle = &name[inner + 1..last];
+    let looks_like_extension = !middle.is_empty()
+        && middle.len() <= 4
+        && middle.chars().all(|c| c.is_ascii_alphanumeric())
+        && !middle.chars().all(|c| c.is_ascii_digit());
+    (looks_like_extension && last + 1 < name.len()).then(|| &name[inner + 1..])
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs::Metadata;
 use std::time::SystemTime;
 use crate::error::SpacemanError;
@@ -10,7 +10,15 @@ pub struct FileEntry {
     pub is_dir: bool,
     pub modified: SystemTime,
     pub permissions: String,
+    /// Owner's user id, 0 for placeholders that were never stat'ed
+    pub uid: u32,
     pub name: String,
+    /// Matched an exclude rule, so it was neither stat'ed nor sized
+    pub skipped: bool,
+    /// Synthetic entry totalling the ignored paths of its directory
+    pub ignored_bucket: bool,
+    /// Tool that regenerates this directory, if it is a known build artifact
+    pub artifact: Option<String>,
 }
 
 impl FileEntry {
@@ -22,6 +30,7 @@ impl FileEntry {
             .to_string();
 
         let permissions = format_permissions(&metadata);
+        let uid = std::os::unix::fs::MetadataExt::uid(&metadata);
         
         Ok(Self {
             path,
@@ -31,9 +40,51 @@ impl FileEntry {
                 .modified()
                 .map_err(|e| SpacemanError::MetadataError(format!("Failed to get modified time: {}", e)))?,
             permissions,
+            uid,
             name,
+            skipped: false,
+            ignored_bucket: false,
+            artifact: None,
         })
     }
+
+    /// Placeholder for a path the scanner skipped without reading metadata.
+    pub fn skipped(path: PathBuf, is_dir: bool) -> Self {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        Self {
+            path,
+            size: 0,
+            is_dir,
+            modified: SystemTime::UNIX_EPOCH,
+            permissions: String::new(),
+            uid: 0,
+            name,
+            skipped: true,
+            ignored_bucket: false,
+            artifact: None,
+        }
+    }
+
+    /// Synthetic entry standing in for everything ignore files match in `dir`.
+    pub fn ignored_bucket(dir: &Path, size: u64) -> Self {
+        Self {
+            path: dir.join("(ignored)"),
+            size,
+            is_dir: false,
+            modified: SystemTime::UNIX_EPOCH,
+            permissions: String::new(),
+            uid: 0,
+            name: "(ignored)".to_string(),
+            skipped: false,
+            ignored_bucket: true,
+            artifact: None,
+        }
+    }
 }
 
 fn format_permissions(metadata: &Metadata) -> String {
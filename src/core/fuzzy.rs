@@ -0,0 +1,79 @@
+/// A case-insensitive match of a query inside some text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Higher is better, compared in order: any substring beats any
+    /// scattered match, then fewer skipped characters, then an earlier start
+    pub score: (bool, i64, i64),
+    /// Char indices of `text` that matched, for highlighting
+    pub positions: Vec<usize>,
+}
+
+/// Matches `query` against `text` as a substring if possible, otherwise as
+/// a subsequence of its characters. An empty query matches everything.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.chars().map(fold).collect();
+    let text: Vec<char> = text.chars().map(fold).collect();
+    if query.is_empty() {
+        return Some(FuzzyMatch { score: (true, 0, 0), positions: Vec::new() });
+    }
+    if query.len() > text.len() {
+        return None;
+    }
+
+    if let Some(start) = text.windows(query.len()).position(|window| window == query.as_slice()) {
+        return Some(FuzzyMatch {
+            score: (true, 0, -(start as i64)),
+            positions: (start..start + query.len()).collect(),
+        });
+    }
+
+    let mut positions = Vec::with_capacity(query.len());
+    let mut wanted = query.iter().peekable();
+    for (i, c) in text.iter().enumerate() {
+        if wanted.peek() == Some(&c) {
+            positions.push(i);
+            wanted.next();
+        }
+    }
+    if wanted.peek().is_some() {
+        return None;
+    }
+
+    let first = positions[0] as i64;
+    let gaps = (positions[positions.len() - 1] - positions[0] + 1 - positions.len()) as i64;
+    Some(FuzzyMatch {
+        score: (false, -gaps, -first),
+        positions,
+    })
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substring_far_into_a_long_path_beats_a_scattered_match() {
+        let long = format!("{}/target.log", "d".repeat(600));
+        let substring = fuzzy_match("target", &long).unwrap();
+        let scattered = fuzzy_match("target", "t/a/r/g/e/t").unwrap();
+        assert!(substring.score > scattered.score);
+        assert_eq!(substring.positions, (601..607).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn tighter_scattered_matches_rank_first() {
+        let tight = fuzzy_match("abc", "xxxxxxxxa-bc").unwrap();
+        let loose = fuzzy_match("abc", "a---b---c").unwrap();
+        assert!(tight.score > loose.score);
+    }
+
+    #[test]
+    fn matching_ignores_case_and_needs_every_character() {
+        assert_eq!(fuzzy_match("README", "docs/readme.md").unwrap().positions, (5..11).collect::<Vec<_>>());
+        assert!(fuzzy_match("xyz", "docs/readme.md").is_none());
+    }
+}
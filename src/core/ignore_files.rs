@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+
+/// Ignore files read at each directory level. Later files take precedence,
+/// so `.spacemanignore` can re-include what `.gitignore` excludes.
+const IGNORE_FILES: [&str; 3] = [".gitignore", ".ignore", ".spacemanignore"];
+
+/// What the scanner does with paths matched by an ignore file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreMode {
+    /// Leave ignored paths out of listings and sizes
+    Hide,
+    /// Total ignored paths into one synthetic entry per directory
+    Group,
+}
+
+/// Lazily loaded, per-directory ignore file matchers. Clones share the
+/// loaded matchers, so scanners handed to worker threads reuse them.
+#[derive(Default, Clone)]
+pub struct IgnoreFiles {
+    /// Ignore files above this directory do not apply
+    boundary: Option<PathBuf>,
+    matchers: Arc<RwLock<HashMap<PathBuf, Arc<DirRules>>>>,
+}
+
+/// The ignore files of one directory.
+struct DirRules {
+    matcher: Gitignore,
+    /// Git reads no `.gitignore` above a repository's root
+    repo_root: bool,
+}
+
+impl IgnoreFiles {
+    /// Ignore files that apply to a scan of `root`: those from the root of
+    /// the git repository enclosing it, or from `root` down outside of one.
+    pub fn new(root: &Path) -> Self {
+        let boundary = root.ancestors().find(|dir| dir.join(".git").exists()).unwrap_or(root);
+        Self {
+            boundary: Some(boundary.to_path_buf()),
+            matchers: Arc::default(),
+        }
+    }
+
+    /// Checks `path` against the ignore files of the directories above it,
+    /// nearest first, up to the boundary or a repository root. The first
+    /// file with an opinion decides. Nothing above the boundary is read, so
+    /// paths outside it, such as parents browsed to later, are never ignored.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for dir in path.ancestors().skip(1) {
+            if let Some(boundary) = &self.boundary
+                && !dir.starts_with(boundary)
+            {
+                break;
+            }
+            let rules = self.rules(dir);
+            match rules.matcher.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+            if rules.repo_root || self.boundary.as_deref() == Some(dir) {
+                break;
+            }
+        }
+        false
+    }
+
+    /// Forgets loaded matchers so edited ignore files are picked up.
+    pub fn clear(&self) {
+        if let Ok(mut matchers) = self.matchers.write() {
+            matchers.clear();
+        }
+    }
+
+    fn rules(&self, dir: &Path) -> Arc<DirRules> {
+        if let Ok(matchers) = self.matchers.read()
+            && let Some(rules) = matchers.get(dir)
+        {
+            return rules.clone();
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        for name in IGNORE_FILES {
+            let file = dir.join(name);
+            if file.is_file() {
+                // Unreadable lines are skipped, the rest of the file still applies
+                let _ = builder.add(file);
+            }
+        }
+        let rules = Arc::new(DirRules {
+            matcher: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+            repo_root: dir.join(".git").exists(),
+        });
+
+        if let Ok(mut matchers) = self.matchers.write() {
+            matchers.insert(dir.to_path_buf(), rules.clone());
+        }
+        rules
+    }
+}
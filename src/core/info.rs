@@ -0,0 +1,165 @@
+use std::ffi::CStr;
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::SpacemanError;
+
+/// Everything `stat` knows about one path, loaded when the info panel
+/// asks for it rather than kept on every `FileEntry`.
+#[derive(Debug, Clone)]
+pub struct EntryInfo {
+    pub path: PathBuf,
+    pub file_type: &'static str,
+    pub symlink_target: Option<PathBuf>,
+    pub inode: u64,
+    pub device: (u32, u32),
+    pub links: u64,
+    pub uid: u32,
+    pub owner: Option<String>,
+    pub gid: u32,
+    pub group: Option<String>,
+    pub mode: u32,
+    pub accessed: SystemTime,
+    pub modified: SystemTime,
+    pub changed: SystemTime,
+    /// Birth time, where the filesystem records it
+    pub created: Option<SystemTime>,
+    pub apparent_size: u64,
+    pub allocated_size: u64,
+    pub block_size: u64,
+}
+
+impl EntryInfo {
+    /// Reads the metadata of `path` itself, not of a symlink's target.
+    pub fn load(path: &Path) -> Result<Self, SpacemanError> {
+        let metadata = fs::symlink_metadata(path)?;
+        let file_type = metadata.file_type();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file_type: if file_type.is_dir() {
+                "directory"
+            } else if file_type.is_symlink() {
+                "symbolic link"
+            } else if file_type.is_fifo() {
+                "fifo"
+            } else if file_type.is_socket() {
+                "socket"
+            } else if file_type.is_block_device() {
+                "block device"
+            } else if file_type.is_char_device() {
+                "character device"
+            } else {
+                "regular file"
+            },
+            symlink_target: file_type.is_symlink().then(|| fs::read_link(path).ok()).flatten(),
+            inode: metadata.ino(),
+            device: (libc::major(metadata.dev()), libc::minor(metadata.dev())),
+            links: metadata.nlink(),
+            uid: metadata.uid(),
+            owner: user_name(metadata.uid()),
+            gid: metadata.gid(),
+            group: group_name(metadata.gid()),
+            mode: metadata.mode(),
+            accessed: timestamp(metadata.atime(), metadata.atime_nsec()),
+            modified: timestamp(metadata.mtime(), metadata.mtime_nsec()),
+            changed: timestamp(metadata.ctime(), metadata.ctime_nsec()),
+            // std uses statx for this on Linux
+            created: metadata.created().ok(),
+            apparent_size: metadata.len(),
+            allocated_size: metadata.blocks() * 512,
+            block_size: metadata.blksize(),
+        })
+    }
+
+    /// The mode as `ls -l` shows it, including setuid, setgid and sticky bits.
+    pub fn symbolic_mode(&self) -> String {
+        let kind = match self.mode & libc::S_IFMT {
+            libc::S_IFDIR => 'd',
+            libc::S_IFLNK => 'l',
+            libc::S_IFIFO => 'p',
+            libc::S_IFSOCK => 's',
+            libc::S_IFBLK => 'b',
+            libc::S_IFCHR => 'c',
+            _ => '-',
+        };
+        let mut symbolic = String::with_capacity(10);
+        symbolic.push(kind);
+        for (shift, special, set, unset) in [(6, 0o4000, 's', 'S'), (3, 0o2000, 's', 'S'), (0, 0o1000, 't', 'T')] {
+            let bits = (self.mode >> shift) & 0o7;
+            symbolic.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+            symbolic.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+            symbolic.push(match (bits & 0o1 != 0, self.mode & special != 0) {
+                (true, true) => set,
+                (false, true) => unset,
+                (true, false) => 'x',
+                (false, false) => '-',
+            });
+        }
+        symbolic
+    }
+
+    pub fn octal_mode(&self) -> String {
+        format!("{:04o}", self.mode & 0o7777)
+    }
+}
+
+/// The time `seconds` and `nanos` after the epoch. Nanoseconds always count
+/// forward, also from a negative number of seconds.
+fn timestamp(seconds: i64, nanos: i64) -> SystemTime {
+    let nanos = Duration::from_nanos(nanos.clamp(0, 999_999_999) as u64);
+    let whole = Duration::from_secs(seconds.unsigned_abs());
+    if seconds >= 0 {
+        UNIX_EPOCH + whole + nanos
+    } else {
+        UNIX_EPOCH - whole + nanos
+    }
+}
+
+fn user_name(uid: u32) -> Option<String> {
+    let mut buffer = vec![0; 4096];
+    // SAFETY: passwd is a plain C struct, valid when zeroed
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local, and the buffer length
+    // passed matches the buffer
+    let status = unsafe { libc::getpwuid_r(uid, &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if status != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: on success pw_name points to a NUL-terminated string in `buffer`
+    Some(unsafe { CStr::from_ptr(passwd.pw_name) }.to_string_lossy().into_owned())
+}
+
+/// Looks up the user id for a login name.
+pub fn user_id(name: &str) -> Option<u32> {
+    let name = std::ffi::CString::new(name).ok()?;
+    let mut buffer = vec![0; 4096];
+    // SAFETY: passwd is a plain C struct, valid when zeroed
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local, and the buffer length
+    // passed matches the buffer
+    let status = unsafe { libc::getpwnam_r(name.as_ptr(), &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if status != 0 || result.is_null() {
+        return None;
+    }
+    Some(passwd.pw_uid)
+}
+
+fn group_name(gid: u32) -> Option<String> {
+    let mut buffer = vec![0; 4096];
+    // SAFETY: group is a plain C struct, valid when zeroed
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local, and the buffer length
+    // passed matches the buffer
+    let status = unsafe { libc::getgrgid_r(gid, &mut group, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if status != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: on success gr_name points to a NUL-terminated string in `buffer`
+    Some(unsafe { CStr::from_ptr(group.gr_name) }.to_string_lossy().into_owned())
+}
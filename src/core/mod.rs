@@ -1,5 +1,35 @@
+mod artifacts;
+mod caches;
+mod check;
+mod delete;
+mod exclude;
+mod extensions;
 mod file_entry;
+mod fuzzy;
+mod ignore_files;
+mod info;
+mod preview;
+mod projects;
+mod query;
 mod scanner;
+mod thresholds;
+mod transfer;
+mod trash;
 
+pub use artifacts::{summarize_artifacts, ArtifactRule, ArtifactRules, ArtifactSummary};
+pub use caches::known_caches;
+pub use check::CheckRule;
+pub use delete::{spawn_delete, DeleteEvent, DeletedTarget};
+pub use exclude::ExcludeRules;
+pub use extensions::{compound_extension_of, extension_of, ExtensionFilter, ExtensionStats};
 pub use file_entry::FileEntry;
-pub use scanner::Scanner; 
\ No newline at end of file
+pub use fuzzy::fuzzy_match;
+pub use ignore_files::{IgnoreFiles, IgnoreMode};
+pub use info::EntryInfo;
+pub use preview::{spawn_previewer, Preview, PreviewRequest};
+pub use projects::{Project, PROJECT_MARKERS};
+pub use query::Query;
+pub use scanner::Scanner;
+pub use thresholds::Thresholds;
+pub use transfer::{spawn_archive, spawn_move, spawn_trash, TransferEvent};
+pub use trash::{list_trash, restore, trash_path, TrashItem}; 
\ No newline at end of file
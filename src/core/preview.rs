@@ -0,0 +1,287 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use flate2::read::GzDecoder;
+
+use crate::core::Scanner;
+
+/// Bytes read from a file to decide how to show it.
+const PREVIEW_BYTES: u64 = 16 * 1024;
+/// Bytes shown in a hex dump.
+const HEX_BYTES: usize = 512;
+/// Members listed from an archive before giving up.
+const ARCHIVE_MEMBERS: usize = 500;
+/// Compressed bytes read while listing a tarball, enough for the headers
+/// near its start without decompressing a large archive. A zip's central
+/// directory is read up to the same limit.
+const ARCHIVE_BYTES: u64 = 4 * PREVIEW_BYTES;
+/// Bytes at the end of a zip that hold its end record: the record itself
+/// and the longest comment it may carry.
+const ZIP_TAIL_BYTES: u64 = 22 + u16::MAX as u64;
+/// Children shown in a directory breakdown.
+const DIRECTORY_CHILDREN: usize = 20;
+/// Entries walked while sizing a directory's children.
+const DIRECTORY_ENTRIES: usize = 20_000;
+
+/// What the preview pane shows for one path.
+#[derive(Debug, Clone)]
+pub enum Preview {
+    Text(Vec<String>),
+    Hex(Vec<String>),
+    Archive { members: Vec<(String, u64)>, truncated: bool },
+    /// `truncated` when the sizes are lower bounds from a partial walk
+    Directory { children: Vec<(PathBuf, u64, bool)>, total: u64, truncated: bool },
+    Message(String),
+}
+
+/// A path to preview, with the scanner settings to size directories by.
+pub type PreviewRequest = (PathBuf, Scanner);
+
+/// Builds the preview for `path`, reading at most a bounded prefix of files
+/// and walking a bounded number of entries below directories. Directories
+/// are broken down with `scanner` so its excludes apply.
+pub fn load_preview(path: &Path, scanner: &Scanner) -> Preview {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return Preview::Message(e.to_string()),
+    };
+
+    if metadata.is_dir() {
+        return directory_preview(path, scanner);
+    }
+    if metadata.file_type().is_symlink() {
+        return match std::fs::read_link(path) {
+            Ok(target) => Preview::Message(format!("Symbolic link to {}", target.display())),
+            Err(e) => Preview::Message(e.to_string()),
+        };
+    }
+    if !metadata.is_file() {
+        return Preview::Message("Special file".to_string());
+    }
+    if let Some(preview) = archive_preview(path) {
+        return preview;
+    }
+
+    let mut bytes = Vec::new();
+    if let Err(e) = File::open(path).and_then(|f| f.take(PREVIEW_BYTES).read_to_end(&mut bytes)) {
+        return Preview::Message(e.to_string());
+    }
+    if bytes.is_empty() {
+        return Preview::Message("Empty file".to_string());
+    }
+    match text_lines(&bytes) {
+        Some(lines) => Preview::Text(lines),
+        None => Preview::Hex(hex_dump(&bytes[..bytes.len().min(HEX_BYTES)])),
+    }
+}
+
+/// Runs previews on a background thread. Requests that pile up while one is
+/// being built are skipped, so only the latest selection is loaded.
+pub fn spawn_previewer() -> (Sender<PreviewRequest>, Receiver<(PathBuf, Preview)>) {
+    let (request_tx, request_rx) = mpsc::channel::<PreviewRequest>();
+    let (result_tx, result_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        while let Ok(mut request) = request_rx.recv() {
+            while let Ok(newer) = request_rx.try_recv() {
+                request = newer;
+            }
+            let (path, scanner) = request;
+            let preview = load_preview(&path, &scanner);
+            if result_tx.send((path, preview)).is_err() {
+                break;
+            }
+        }
+    });
+
+    (request_tx, result_rx)
+}
+
+fn directory_preview(path: &Path, scanner: &Scanner) -> Preview {
+    if let Err(e) = std::fs::read_dir(path) {
+        return Preview::Message(e.to_string());
+    }
+    let (mut children, total, truncated) = scanner.measure_children(path, DIRECTORY_ENTRIES);
+    if children.is_empty() {
+        return Preview::Message("Empty directory".to_string());
+    }
+    children.sort_by_key(|(_, size, _)| std::cmp::Reverse(*size));
+    children.truncate(DIRECTORY_CHILDREN);
+    Preview::Directory { children, total, truncated }
+}
+
+fn archive_preview(path: &Path) -> Option<Preview> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    let listing = if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        let file = File::open(path).ok()?.take(ARCHIVE_BYTES);
+        tar_members(GzDecoder::new(BufReader::new(file)))
+    } else if name.ends_with(".tar") {
+        tar_members(File::open(path).ok()?.take(ARCHIVE_BYTES))
+    } else if [".zip", ".jar", ".whl", ".apk"].iter().any(|ext| name.ends_with(ext)) {
+        zip_members(path)
+    } else {
+        return None;
+    };
+
+    Some(match listing {
+        Ok((members, truncated)) => Preview::Archive { members, truncated },
+        Err(e) => Preview::Message(format!("Unreadable archive: {}", e)),
+    })
+}
+
+type Members = (Vec<(String, u64)>, bool);
+
+fn tar_members<R: Read>(reader: R) -> Result<Members, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut members = Vec::new();
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        if members.len() == ARCHIVE_MEMBERS {
+            return Ok((members, true));
+        }
+        match entry {
+            Ok(entry) => {
+                let name = entry.path().map_or_else(|_| "?".into(), |p| p.display().to_string());
+                members.push((name, entry.size()));
+            }
+            // Hitting the read limit mid-archive still leaves a useful listing
+            Err(_) if !members.is_empty() => return Ok((members, true)),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok((members, false))
+}
+
+/// Lists a zip from its central directory, reading the end record and at
+/// most `ARCHIVE_BYTES` of the directory itself.
+fn zip_members(path: &Path) -> Result<Members, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(len.saturating_sub(ZIP_TAIL_BYTES)))
+        .and_then(|_| (&mut file).take(ZIP_TAIL_BYTES).read_to_end(&mut tail))
+        .map_err(|e| e.to_string())?;
+
+    let end = tail
+        .windows(4)
+        .rposition(|w| w == b"PK\x05\x06")
+        .filter(|&end| end + 22 <= tail.len())
+        .ok_or("No end of central directory")?;
+    let mut count = le_u16(&tail, end + 10) as u64;
+    let mut offset = le_u32(&tail, end + 16) as u64;
+    if count == u16::MAX as u64 || offset == u32::MAX as u64 {
+        // Zip64 keeps the real values in a record its locator points to
+        let locator = end
+            .checked_sub(20)
+            .filter(|&locator| tail[locator..].starts_with(b"PK\x06\x07"))
+            .ok_or("No zip64 end of central directory locator")?;
+        let mut record = [0; 56];
+        file.seek(SeekFrom::Start(le_u64(&tail, locator + 8)))
+            .and_then(|_| file.read_exact(&mut record))
+            .map_err(|e| e.to_string())?;
+        if !record.starts_with(b"PK\x06\x06") {
+            return Err("No zip64 end of central directory".to_string());
+        }
+        count = le_u64(&record, 32);
+        offset = le_u64(&record, 48);
+    }
+
+    let mut directory = Vec::new();
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| file.take(ARCHIVE_BYTES).read_to_end(&mut directory))
+        .map_err(|e| e.to_string())?;
+
+    let mut members = Vec::new();
+    let mut at = 0;
+    while (members.len() as u64) < count && members.len() < ARCHIVE_MEMBERS {
+        // A record cut off by the read limit ends the listing
+        let Some(header) = directory.get(at..at + 46).filter(|h| h.starts_with(b"PK\x01\x02")) else {
+            break;
+        };
+        let name_len = le_u16(header, 28) as usize;
+        let extra_len = le_u16(header, 30) as usize;
+        let comment_len = le_u16(header, 32) as usize;
+        let name_start = at + 46;
+        let Some(name) = directory.get(name_start..name_start + name_len) else {
+            break;
+        };
+        let extra = directory.get(name_start + name_len..name_start + name_len + extra_len).unwrap_or(&[]);
+        let mut size = le_u32(header, 24) as u64;
+        if size == u32::MAX as u64 {
+            size = zip64_size(extra).unwrap_or(size);
+        }
+        members.push((String::from_utf8_lossy(name).into_owned(), size));
+        at = name_start + name_len + extra_len + comment_len;
+    }
+    if members.is_empty() && count > 0 {
+        return Err("Unreadable central directory".to_string());
+    }
+    let truncated = (members.len() as u64) < count;
+    Ok((members, truncated))
+}
+
+/// The uncompressed size from a zip64 extended information field, which
+/// comes first in it when the header's own size is saturated.
+fn zip64_size(mut extra: &[u8]) -> Option<u64> {
+    while extra.len() >= 4 {
+        let (id, len) = (le_u16(extra, 0), le_u16(extra, 2) as usize);
+        let data = extra.get(4..4 + len)?;
+        if id == 0x0001 && data.len() >= 8 {
+            return Some(le_u64(data, 0));
+        }
+        extra = &extra[4 + len..];
+    }
+    None
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap_or_default())
+}
+
+fn le_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap_or_default())
+}
+
+/// Splits `bytes` into display lines if it looks like UTF-8 text. A
+/// multi-byte character cut off by the read limit does not count against it.
+fn text_lines(bytes: &[u8]) -> Option<Vec<String>> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    Some(
+        text.lines()
+            .map(|line| {
+                line.replace('\t', "    ")
+                    .chars()
+                    .filter(|c| !c.is_control())
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  {}", i * 16, hex.join(" "), ascii)
+        })
+        .collect()
+}
@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// Files or directories whose presence makes a directory a project root.
+pub const PROJECT_MARKERS: [&str; 4] = [".git", "Cargo.toml", "package.json", "pyproject.toml"];
+
+/// A project root found during a scan.
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Bytes in build artifact and cache directories inside the project
+    pub artifact_size: u64,
+    /// Newest modification time of any source file, outside artifacts and `.git`
+    pub last_modified: SystemTime,
+}
+
+impl Project {
+    pub fn untouched_for(&self) -> Duration {
+        self.last_modified.elapsed().unwrap_or_default()
+    }
+
+    /// Ranks projects so that large ones untouched for longest come first.
+    pub fn staleness(&self) -> f64 {
+        let days = self.untouched_for().as_secs_f64() / 86400.0;
+        self.size as f64 * days
+    }
+}
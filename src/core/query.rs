@@ -0,0 +1,567 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use chrono::{Local, NaiveDate};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+
+use crate::core::{info::user_id, ExtensionFilter, FileEntry};
+use crate::utils::{parse_age, parse_size};
+
+const FIELDS: &str = "size, mtime, name, path, ext, owner, type";
+
+/// A filter expression such as
+/// `size > 1G and mtime < -30d and ext in (log, gz) and owner = ci`.
+///
+/// Tests compare a field with a value and combine with `and`, `or`, `not`
+/// and parentheses. `size` takes units like `500K` or `1.5G`. `mtime` takes
+/// an age in the past like `-30d` or a date like `2024-01-31`, so
+/// `mtime < -30d` means "modified more than 30 days ago". `name` and `path`
+/// match a glob with `=` or a regex with `~`, `ext` and `owner` compare with
+/// `=` or `in (...)`, and `type` is `file` or `dir`. Path globs are relative
+/// to the scanned directory unless they start with `/`; path regexes see
+/// the whole path.
+#[derive(Debug, Clone)]
+pub struct Query {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Test(Test),
+}
+
+#[derive(Debug, Clone)]
+enum Test {
+    Size(Comparison, u64),
+    Modified(Comparison, SystemTime),
+    Name(Vec<Pattern>),
+    Path(Vec<Pattern>),
+    Ext(ExtensionFilter),
+    Owner(Vec<u32>),
+    Type { dir: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering == Ordering::Equal,
+            Comparison::Ne => ordering != Ordering::Equal,
+            Comparison::Lt => ordering == Ordering::Less,
+            Comparison::Le => ordering != Ordering::Greater,
+            Comparison::Gt => ordering == Ordering::Greater,
+            Comparison::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Compare(Comparison),
+    Match,
+    NotMatch,
+    In,
+    NotIn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Open,
+    Close,
+    Comma,
+    Operator(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "'{}'", word),
+            Token::Quoted(text) => write!(f, "\"{}\"", text),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+            Token::Operator(op) => write!(f, "'{}'", op),
+        }
+    }
+}
+
+impl Query {
+    /// Whether `entry`, found scanning `root`, satisfies the expression.
+    /// Placeholders for excluded or ignored paths never do, since nothing
+    /// is known about them.
+    pub fn matches(&self, entry: &FileEntry, root: &Path) -> bool {
+        !entry.skipped && !entry.ignored_bucket && self.expr.matches(entry, root)
+    }
+
+    /// Whether the expression tests `type`, i.e. says whether directories
+    /// should be listed at all.
+    pub fn mentions_type(&self) -> bool {
+        self.expr.mentions_type()
+    }
+}
+
+impl Expr {
+    fn matches(&self, entry: &FileEntry, root: &Path) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(entry, root) && right.matches(entry, root),
+            Expr::Or(left, right) => left.matches(entry, root) || right.matches(entry, root),
+            Expr::Not(inner) => !inner.matches(entry, root),
+            Expr::Test(test) => test.matches(entry, root),
+        }
+    }
+
+    fn mentions_type(&self) -> bool {
+        match self {
+            Expr::And(left, right) | Expr::Or(left, right) => left.mentions_type() || right.mentions_type(),
+            Expr::Not(inner) => inner.mentions_type(),
+            Expr::Test(test) => matches!(test, Test::Type { .. }),
+        }
+    }
+}
+
+impl Test {
+    fn matches(&self, entry: &FileEntry, root: &Path) -> bool {
+        match self {
+            Test::Size(comparison, size) => comparison.holds(entry.size.cmp(size)),
+            Test::Modified(comparison, time) => comparison.holds(entry.modified.cmp(time)),
+            Test::Name(patterns) => patterns.iter().any(|pattern| match pattern {
+                Pattern::Glob(glob) => glob.is_match(&entry.name),
+                Pattern::Regex(regex) => regex.is_match(&entry.name),
+            }),
+            Test::Path(patterns) => patterns.iter().any(|pattern| match pattern {
+                Pattern::Glob(glob) if glob.glob().glob().starts_with('/') => glob.is_match(&entry.path),
+                Pattern::Glob(glob) => glob.is_match(entry.path.strip_prefix(root).unwrap_or(&entry.path)),
+                Pattern::Regex(regex) => regex.is_match(&entry.path.to_string_lossy()),
+            }),
+            Test::Ext(filter) => !entry.is_dir && filter.matches(&entry.path),
+            Test::Owner(uids) => uids.contains(&entry.uid),
+            Test::Type { dir } => entry.is_dir == *dir,
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+            end: s.chars().count() + 1,
+        };
+        let expr = parser.parse_or()?;
+        if let Some((token, column)) = parser.tokens.get(parser.position) {
+            return Err(error(*column, format!("Unexpected {}, expected 'and' or 'or'", token)));
+        }
+        Ok(Self {
+            source: s.trim().to_string(),
+            expr,
+        })
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn error(column: usize, message: String) -> String {
+    format!("column {}: {}", column, message)
+}
+
+/// Splits the expression into tokens paired with their 1-based column.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let next = chars.get(i + 1).copied();
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '(' | ')' | ',' => {
+                tokens.push((
+                    match c {
+                        '(' => Token::Open,
+                        ')' => Token::Close,
+                        _ => Token::Comma,
+                    },
+                    column,
+                ));
+                i += 1;
+            }
+            '<' | '>' | '=' | '!' | '~' => {
+                let op = match (c, next) {
+                    ('<', Some('=')) => "<=",
+                    ('>', Some('=')) => ">=",
+                    ('!', Some('=')) => "!=",
+                    ('=', Some('=')) => "==",
+                    ('!', Some('~')) => "!~",
+                    ('<', _) => "<",
+                    ('>', _) => ">",
+                    ('=', _) => "=",
+                    ('~', _) => "~",
+                    _ => return Err(error(column, "Expected '!=' or '!~'".to_string())),
+                };
+                tokens.push((Token::Operator(op), column));
+                i += op.len();
+            }
+            '"' | '\'' => {
+                let Some(length) = chars[i + 1..].iter().position(|&q| q == c) else {
+                    return Err(error(column, format!("Unterminated string starting with {}", c)));
+                };
+                tokens.push((Token::Quoted(chars[i + 1..i + 1 + length].iter().collect()), column));
+                i += length + 2;
+            }
+            _ => {
+                let length = chars[i..]
+                    .iter()
+                    .position(|&w| w.is_whitespace() || "()<>=!~,\"'".contains(w))
+                    .unwrap_or(chars.len() - i);
+                tokens.push((Token::Word(chars[i..i + length].iter().collect()), column));
+                i += length;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over `or` < `and` < `not` < tests and parentheses.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// Column just past the input, for errors at the end
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.position).map_or(self.end, |(_, column)| *column)
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.keyword("or") {
+            self.position += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while self.keyword("and") {
+            self.position += 1;
+            left = Expr::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            self.position += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        let column = self.column();
+        match self.next() {
+            Some((Token::Open, _)) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some((Token::Close, _)) => Ok(expr),
+                    _ => Err(error(column, "Unclosed '('".to_string())),
+                }
+            }
+            Some((Token::Word(field), _)) => self.parse_test(&field, column),
+            Some((token, _)) => Err(error(column, format!("Unexpected {}, expected a test such as size > 1G", token))),
+            None => Err(error(column, "Expected a test such as size > 1G".to_string())),
+        }
+    }
+
+    fn parse_test(&mut self, field: &str, column: usize) -> Result<Expr, String> {
+        let field = field.to_ascii_lowercase();
+        if !FIELDS.split(", ").any(|known| known == field) {
+            return Err(error(column, format!("Unknown field '{}', expected one of {}", field, FIELDS)));
+        }
+
+        let operator_column = self.column();
+        let operator = match self.next() {
+            Some((Token::Operator(op), _)) => match op {
+                "=" | "==" => Operator::Compare(Comparison::Eq),
+                "!=" => Operator::Compare(Comparison::Ne),
+                "<" => Operator::Compare(Comparison::Lt),
+                "<=" => Operator::Compare(Comparison::Le),
+                ">" => Operator::Compare(Comparison::Gt),
+                ">=" => Operator::Compare(Comparison::Ge),
+                "~" => Operator::Match,
+                _ => Operator::NotMatch,
+            },
+            Some((Token::Word(word), _)) if word.eq_ignore_ascii_case("in") => Operator::In,
+            Some((Token::Word(word), _)) if word.eq_ignore_ascii_case("not") && self.keyword("in") => {
+                self.position += 1;
+                Operator::NotIn
+            }
+            _ => return Err(error(operator_column, format!("Expected an operator after '{}'", field))),
+        };
+
+        let values = match operator {
+            Operator::In | Operator::NotIn => self.parse_list()?,
+            _ => vec![self.parse_value(&field)?],
+        };
+        let unsupported = || {
+            let operators = match field.as_str() {
+                "size" => "=, !=, <, <=, > or >=",
+                "mtime" => "<, <=, > or >=",
+                "name" | "path" => "=, !=, ~, !~, in or not in",
+                "type" => "= or !=",
+                _ => "=, !=, in or not in",
+            };
+            error(operator_column, format!("'{}' only supports {}", field, operators))
+        };
+
+        let (test, negated) = match (field.as_str(), operator) {
+            ("size", Operator::Compare(comparison)) => {
+                let (value, column) = &values[0];
+                let size = parse_size(value).map_err(|e| error(*column, e))?;
+                (Test::Size(comparison, size), false)
+            }
+            ("mtime", Operator::Compare(comparison)) if !matches!(comparison, Comparison::Eq | Comparison::Ne) => {
+                let (value, column) = &values[0];
+                (Test::Modified(comparison, parse_time(value).map_err(|e| error(*column, e))?), false)
+            }
+            ("name" | "path", Operator::Compare(Comparison::Eq | Comparison::Ne) | Operator::In | Operator::NotIn) => {
+                let patterns = values
+                    .iter()
+                    .map(|(value, column)| {
+                        Glob::new(value)
+                            .map(|glob| Pattern::Glob(glob.compile_matcher()))
+                            .map_err(|e| error(*column, format!("Invalid glob: {}", e.kind())))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let negated = matches!(operator, Operator::Compare(Comparison::Ne) | Operator::NotIn);
+                let test = if field == "name" { Test::Name(patterns) } else { Test::Path(patterns) };
+                (test, negated)
+            }
+            ("name" | "path", Operator::Match | Operator::NotMatch) => {
+                let (value, column) = &values[0];
+                let regex = Regex::new(value).map_err(|e| {
+                    let reason = e.to_string();
+                    let reason = reason.lines().last().unwrap_or_default().trim_start_matches("error: ");
+                    error(*column, format!("Invalid regex: {}", reason))
+                })?;
+                let patterns = vec![Pattern::Regex(regex)];
+                let test = if field == "name" { Test::Name(patterns) } else { Test::Path(patterns) };
+                (test, operator == Operator::NotMatch)
+            }
+            ("ext", Operator::Compare(Comparison::Eq | Comparison::Ne) | Operator::In | Operator::NotIn) => {
+                let extensions: Vec<&str> = values.iter().map(|(value, _)| value.as_str()).collect();
+                let filter = ExtensionFilter::from_list(&extensions, true)
+                    .ok_or_else(|| error(values[0].1, "Expected an extension such as log".to_string()))?;
+                (Test::Ext(filter), matches!(operator, Operator::Compare(Comparison::Ne) | Operator::NotIn))
+            }
+            ("owner", Operator::Compare(Comparison::Eq | Comparison::Ne) | Operator::In | Operator::NotIn) => {
+                let uids = values
+                    .iter()
+                    .map(|(value, column)| {
+                        value
+                            .parse()
+                            .ok()
+                            .or_else(|| user_id(value))
+                            .ok_or_else(|| error(*column, format!("Unknown user '{}'", value)))
+                    })
+                    .collect::<Result<Vec<u32>, _>>()?;
+                (Test::Owner(uids), matches!(operator, Operator::Compare(Comparison::Ne) | Operator::NotIn))
+            }
+            ("type", Operator::Compare(comparison @ (Comparison::Eq | Comparison::Ne))) => {
+                let (value, column) = &values[0];
+                let dir = match value.to_ascii_lowercase().as_str() {
+                    "file" | "f" => false,
+                    "dir" | "directory" | "d" => true,
+                    _ => return Err(error(*column, format!("Expected file or dir, got '{}'", value))),
+                };
+                (Test::Type { dir }, comparison == Comparison::Ne)
+            }
+            _ => return Err(unsupported()),
+        };
+
+        let test = Expr::Test(test);
+        Ok(if negated { Expr::Not(Box::new(test)) } else { test })
+    }
+
+    fn parse_value(&mut self, field: &str) -> Result<(String, usize), String> {
+        let column = self.column();
+        match self.next() {
+            Some((Token::Word(value) | Token::Quoted(value), column)) => Ok((value, column)),
+            _ => Err(error(column, format!("Expected a value for '{}'", field))),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<(String, usize)>, String> {
+        let column = self.column();
+        if self.next().map(|(token, _)| token) != Some(Token::Open) {
+            return Err(error(column, "Expected '(' to start a list".to_string()));
+        }
+        let mut values = Vec::new();
+        loop {
+            let column = self.column();
+            match self.next() {
+                Some((Token::Word(value) | Token::Quoted(value), column)) => values.push((value, column)),
+                _ => return Err(error(column, "Expected a value in the list".to_string())),
+            }
+            let column = self.column();
+            match self.next() {
+                Some((Token::Comma, _)) => {}
+                Some((Token::Close, _)) => return Ok(values),
+                _ => return Err(error(column, "Expected ',' or ')' in the list".to_string())),
+            }
+        }
+    }
+}
+
+/// `-30d` is 30 days ago, `2024-01-31` is the start of that local day.
+fn parse_time(value: &str) -> Result<SystemTime, String> {
+    if let Some(age) = value.strip_prefix('-') {
+        let age = parse_age(age)?;
+        return Ok(SystemTime::now().checked_sub(age).unwrap_or(SystemTime::UNIX_EPOCH));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest())
+        .map(SystemTime::from)
+        .ok_or_else(|| format!("Expected an age like -30d or a date like 2024-01-31, got: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn entry(path: &str, size: u64, age_days: u64, is_dir: bool) -> FileEntry {
+        let path = PathBuf::from(path);
+        FileEntry {
+            name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            path,
+            size,
+            is_dir,
+            modified: SystemTime::now() - Duration::from_secs(age_days * DAY),
+            permissions: String::new(),
+            uid: 0,
+            skipped: false,
+            ignored_bucket: false,
+            artifact: None,
+        }
+    }
+
+    fn matches(query: &str, entry: &FileEntry) -> bool {
+        query.parse::<Query>().unwrap().matches(entry, Path::new("/scan"))
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let small_log = entry("/scan/a.log", 10, 0, false);
+        // Read as `name = "*.log" or (size > 1K and type = dir)`
+        assert!(matches(r#"name = "*.log" or size > 1K and type = dir"#, &small_log));
+        assert!(!matches(r#"(name = "*.log" or size > 1K) and type = dir"#, &small_log));
+    }
+
+    #[test]
+    fn not_applies_to_the_nearest_test() {
+        let log = entry("/scan/a.log", 10, 0, false);
+        assert!(matches("not ext = gz and ext = log", &log));
+        assert!(!matches("not (ext = gz or ext = log)", &log));
+    }
+
+    #[test]
+    fn not_in_excludes_every_listed_value() {
+        assert!(!matches("ext not in (log, gz)", &entry("/scan/a.log", 10, 0, false)));
+        assert!(!matches("ext not in (log, gz)", &entry("/scan/a.gz", 10, 0, false)));
+        assert!(matches("ext not in (log, gz)", &entry("/scan/a.txt", 10, 0, false)));
+        assert!(matches(r#"name not in ("*.log", "*.gz")"#, &entry("/scan/a.txt", 10, 0, false)));
+    }
+
+    #[test]
+    fn path_globs_are_relative_to_the_scan_root() {
+        let nested = entry("/scan/build/out/a.o", 10, 0, false);
+        assert!(matches(r#"path = "build/**""#, &nested));
+        assert!(!matches(r#"path = "scan/**""#, &nested));
+        assert!(matches(r#"path = "/scan/build/**""#, &nested));
+        assert!(matches(r#"path ~ '^/scan/build/'"#, &nested));
+    }
+
+    #[test]
+    fn mtime_compares_ages_in_the_past() {
+        let old = entry("/scan/old", 10, 60, false);
+        let new = entry("/scan/new", 10, 1, false);
+        assert!(matches("mtime < -30d", &old));
+        assert!(!matches("mtime < -30d", &new));
+        assert!(matches("mtime > -30d", &new));
+        assert!(matches("mtime > 1971-01-01", &old));
+    }
+
+    #[test]
+    fn placeholders_never_match() {
+        let mut skipped = entry("/scan/skipped", 0, 0, true);
+        skipped.skipped = true;
+        assert!(!matches("size >= 0", &skipped));
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_column() {
+        let error = |query: &str| query.parse::<Query>().unwrap_err();
+        assert_eq!(error("size > 1X").split(':').next(), Some("column 8"));
+        assert!(error("bogus = 1").starts_with("column 1: Unknown field 'bogus'"));
+        assert!(error("size > 1G and").starts_with("column 14: Expected a test"));
+        assert!(error("mtime = -1d").starts_with("column 7: 'mtime' only supports"));
+        assert!(error("(size > 1G").starts_with("column 1: Unclosed '('"));
+        assert!(error("name = 'a").starts_with("column 8: Unterminated string"));
+        assert!(error("ext in (log gz)").starts_with("column 13: Expected ',' or ')'"));
+    }
+}
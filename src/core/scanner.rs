@@ -1,16 +1,31 @@
-use std::path::PathBuf;
-use walkdir::WalkDir;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use walkdir::{DirEntry, WalkDir};
 use rayon::prelude::*;
 use crate::error::SpacemanError;
-use crate::core::FileEntry;
+use crate::core::{
+    compound_extension_of, extension_of, ArtifactRules, ExcludeRules, ExtensionFilter, ExtensionStats, FileEntry, IgnoreFiles, IgnoreMode,
+    Project, Thresholds, PROJECT_MARKERS,
+};
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::MetadataExt;
 
 const CHUNK_SIZE: usize = 1000;
 
+#[derive(Clone)]
 pub struct Scanner {
     max_depth: usize,
     show_hidden: bool,
-    extension_filter: Option<String>,
+    extension_filter: Option<ExtensionFilter>,
+    excludes: ExcludeRules,
+    ignore_mode: Option<IgnoreMode>,
+    ignore_files: IgnoreFiles,
+    artifact_rules: ArtifactRules,
+    thresholds: Thresholds,
 }
 
 impl Scanner {
@@ -19,29 +34,73 @@ impl Scanner {
             max_depth,
             show_hidden,
             extension_filter: None,
+            excludes: ExcludeRules::default(),
+            ignore_mode: None,
+            ignore_files: IgnoreFiles::default(),
+            artifact_rules: ArtifactRules::default(),
+            thresholds: Thresholds::default(),
         }
     }
 
-    pub fn with_extension_filter(mut self, ext: Option<String>) -> Self {
-        self.extension_filter = ext;
+    pub fn with_extension_filter(mut self, filter: Option<ExtensionFilter>) -> Self {
+        self.extension_filter = filter;
         self
     }
 
-    pub fn scan(&self, path: &str) -> Result<Vec<FileEntry>, SpacemanError> {
+    pub fn with_excludes(mut self, excludes: ExcludeRules) -> Self {
+        self.excludes = excludes;
+        self
+    }
+
+    pub fn with_ignore_mode(mut self, mode: Option<IgnoreMode>) -> Self {
+        self.ignore_mode = mode;
+        self
+    }
+
+    /// Reads ignore files no further up than `root`, or the repository
+    /// enclosing it.
+    pub fn with_ignore_root(mut self, root: &Path) -> Self {
+        self.ignore_files = IgnoreFiles::new(root);
+        self
+    }
+
+    pub fn with_artifact_rules(mut self, rules: ArtifactRules) -> Self {
+        self.artifact_rules = rules;
+        self
+    }
+
+    pub fn with_thresholds(mut self, thresholds: Thresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    pub fn set_extension_filter(&mut self, filter: Option<ExtensionFilter>) {
+        self.extension_filter = filter;
+    }
+
+    /// Lists `path` to the scanner's depth and returns it along with the
+    /// search index of everything below, which is collected by the same
+    /// walk that sizes `path` itself.
+    pub fn scan(&self, path: &str) -> Result<(Vec<FileEntry>, Vec<FileEntry>), SpacemanError> {
         let path_buf = PathBuf::from(path);
+        self.ignore_files.clear();
+        let tree = Mutex::new(Vec::new());
         
         // Pre-allocate the entries vector with a reasonable capacity
         let mut entries = Vec::with_capacity(100);
+        let mut skipped = Vec::new();
+        let mut ignored = Vec::new();
         
         // Use a single WalkDir iterator with optimized settings
-        let walker = WalkDir::new(&path_buf)
+        let mut walker = WalkDir::new(&path_buf)
             .max_depth(self.max_depth)
             .follow_links(false)
             .same_file_system(true)
-            .sort_by(|a, b| a.file_name().cmp(b.file_name()));
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .into_iter();
 
         // Collect entries with optimized filtering
-        for entry in walker {
+        while let Some(entry) = walker.next() {
             let entry = match entry {
                 Ok(e) => e,
                 Err(_) => continue,
@@ -52,24 +111,43 @@ impl Scanner {
                 continue;
             }
 
-            // Extension filter check
-            if let Some(ext) = &self.extension_filter {
-                if !entry
-                    .path()
-                    .extension()
-                    .and_then(|e| e.to_str())
-                    .map(|e| e == ext)
-                    .unwrap_or(false)
-                {
-                    continue;
+            // Excluded paths are reported but never stat'ed or descended into
+            if entry.depth() > 0 && self.excludes.is_excluded(entry.path()) {
+                let is_dir = entry.file_type().is_dir();
+                if is_dir {
+                    walker.skip_current_dir();
                 }
+                skipped.push(FileEntry::skipped(entry.into_path(), is_dir));
+                continue;
+            }
+
+            // Paths matched by an ignore file are hidden, or set aside to be
+            // totalled into the directory's ignored bucket
+            if entry.depth() > 0 && self.is_ignored(&entry) {
+                if entry.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+                if entry.depth() == 1 {
+                    ignored.push(entry);
+                }
+                continue;
+            }
+
+            // Extension filter check, directories are kept and sized by
+            // their matching files below
+            if let Some(filter) = &self.extension_filter
+                && !entry.file_type().is_dir()
+                && !filter.matches(entry.path())
+            {
+                continue;
             }
 
             entries.push(entry);
         }
 
-        // Process entries in chunks with optimized parallelization
-        let results: Vec<_> = entries
+        // Process entries in chunks with optimized parallelization, keeping
+        // the ignored bytes found below each top-level directory
+        let sized: Vec<(FileEntry, u64)> = entries
             .par_chunks(CHUNK_SIZE)
             .flat_map(|chunk| {
                 chunk
@@ -86,37 +164,564 @@ impl Scanner {
                             Err(_) => return None,
                         };
                         
+                        let mut ignored_size = 0;
                         if file_entry.is_dir {
-                            file_entry.size = self.calculate_dir_size(&path);
+                            file_entry.artifact = self.artifact_rules.detect(&path).map(str::to_string);
+                            let mut index = Vec::new();
+                            let index_into = (entry.depth() == 0).then_some(&mut index);
+                            file_entry.size = match &self.extension_filter {
+                                Some(filter) => self.calculate_matching_size(&path, filter, index_into),
+                                None => {
+                                    let (size, ignored) = self.calculate_dir_size(&path, index_into);
+                                    ignored_size = ignored;
+                                    size
+                                }
+                            };
+                            if entry.depth() == 0
+                                && let Ok(mut tree) = tree.lock()
+                            {
+                                *tree = index;
+                            }
+                            if self.extension_filter.is_some() && file_entry.size == 0 {
+                                return None;
+                            }
                         } else {
                             // Use the same block-based calculation as du
                             file_entry.size = metadata.blocks() * 512;
                         }
-                        
-                        Some(file_entry)
+
+                        if entry.depth() != 1 {
+                            ignored_size = 0;
+                        }
+                        Some((file_entry, ignored_size))
                     })
                     .collect::<Vec<_>>()
             })
             .collect();
+        let (mut results, ignored_below): (Vec<FileEntry>, Vec<u64>) = sized.into_iter().unzip();
+
+        // Ignored paths have no matching bytes to show under an extension
+        // filter, so they are only bucketed without one
+        if self.ignore_mode == Some(IgnoreMode::Group) && self.extension_filter.is_none() {
+            let ignored_size: u64 = ignored_below.iter().sum::<u64>()
+                + ignored
+                    .par_iter()
+                    .map(|entry| {
+                        if entry.file_type().is_dir() {
+                            let (size, ignored) = self.calculate_dir_size(entry.path(), None);
+                            size + ignored
+                        } else {
+                            entry.metadata().map(|m| m.blocks() * 512).unwrap_or(0)
+                        }
+                    })
+                    .sum::<u64>();
+            if ignored_size > 0 {
+                results.push(FileEntry::ignored_bucket(&path_buf, ignored_size));
+            }
+        }
+
+        // Sizes are final, so entries below the thresholds can go without
+        // changing what their parents add up to. Directories holding an
+        // admitted entry stay, so nothing is listed without its parent.
+        if !self.thresholds.is_empty() {
+            let kept: HashSet<PathBuf> = results
+                .iter()
+                .filter(|e| self.admits(e))
+                .flat_map(|e| e.path.ancestors().take_while(|dir| dir.starts_with(&path_buf)))
+                .map(Path::to_path_buf)
+                .collect();
+            results.retain(|e| e.path == path_buf || e.ignored_bucket || kept.contains(&e.path));
+        }
 
-        Ok(results)
+        results.extend(skipped);
+        let tree = self.finish_index(tree.into_inner().unwrap_or_default(), &path_buf);
+        Ok((results, tree))
     }
 
-    fn calculate_dir_size(&self, path: &PathBuf) -> u64 {
-        let mut total_size = 0u64;
+    /// Total size of a file or directory and the newest modification time
+    /// anywhere in it, which tells when a cache was last written to better
+    /// than the directory's own time.
+    pub fn measure_with_newest(&self, path: &Path) -> Option<(u64, SystemTime)> {
+        let metadata = std::fs::symlink_metadata(path).ok()?;
+        let mut size = metadata.blocks() * 512;
+        let mut newest = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        if metadata.is_dir() {
+            let walker = WalkDir::new(path)
+                .min_depth(1)
+                .follow_links(false)
+                .same_file_system(true)
+                .into_iter()
+                .filter_entry(|e| !self.excludes.is_excluded(e.path()));
+            for metadata in walker.filter_map(|entry| entry.ok()?.metadata().ok()) {
+                size += metadata.blocks() * 512;
+                if let Ok(modified) = metadata.modified() {
+                    newest = newest.max(modified);
+                }
+            }
+        }
+        Some((size, newest))
+    }
+
+    /// Sizes the immediate children of `path` the way `scan` would, but
+    /// stops after walking `limit` entries. Returns the children, their
+    /// total and whether the walk stopped early, leaving lower bounds.
+    pub fn measure_children(&self, path: &Path, limit: usize) -> (Vec<(PathBuf, u64, bool)>, u64, bool) {
+        let mut children: Vec<(PathBuf, u64, bool)> = Vec::new();
+        let mut total = match (&self.extension_filter, std::fs::symlink_metadata(path)) {
+            (None, Ok(metadata)) => metadata.blocks() * 512,
+            _ => 0,
+        };
+        // Whether the child the walk is currently inside of is listed
+        let mut listed = false;
+        let mut walker = WalkDir::new(path)
+            .min_depth(1)
+            .follow_links(false)
+            .same_file_system(true)
+            .into_iter()
+            .filter_entry(|e| !self.excludes.is_excluded(e.path()));
+
+        let mut walked = 0;
+        while let Some(entry) = walker.next() {
+            if walked == limit {
+                return (children, total, true);
+            }
+            walked += 1;
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if self.is_ignored(&entry) {
+                if entry.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+                if entry.depth() == 1 {
+                    listed = false;
+                }
+                continue;
+            }
+            if entry.depth() == 1 {
+                listed = self.show_hidden || !entry.file_name().to_string_lossy().starts_with('.');
+                if listed {
+                    children.push((entry.path().to_path_buf(), 0, entry.file_type().is_dir()));
+                }
+            }
+
+            let counted = match &self.extension_filter {
+                Some(filter) => entry.file_type().is_file() && filter.matches(entry.path()),
+                None => true,
+            };
+            let size = match entry.metadata() {
+                Ok(metadata) if counted => metadata.blocks() * 512,
+                _ => 0,
+            };
+            total += size;
+            if listed && let Some(child) = children.last_mut() {
+                child.1 += size;
+            }
+        }
+        // Like `scan`, an extension filter lists only what has matching bytes
+        if self.extension_filter.is_some() {
+            children.retain(|(_, size, _)| *size > 0);
+        }
+        (children, total, false)
+    }
+
+    /// Total size of a file or directory, counting ignored paths too.
+    pub fn measure(&self, path: &Path) -> u64 {
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => {
+                let (size, ignored) = self.calculate_dir_size(path, None);
+                size + ignored
+            }
+            Ok(metadata) => metadata.blocks() * 512,
+            Err(_) => 0,
+        }
+    }
+
+    /// Finds every known build artifact or cache directory below `path`,
+    /// largest first. Artifacts are not searched for nested artifacts.
+    pub fn find_artifacts(&self, path: &Path) -> Vec<FileEntry> {
+        let mut found = Vec::new();
+        let mut walker = WalkDir::new(path)
+            .follow_links(false)
+            .same_file_system(true)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !self.excludes.is_excluded(e.path()));
+
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            if let Some(tool) = self.artifact_rules.detect(entry.path()) {
+                found.push((entry.into_path(), tool.to_string()));
+                walker.skip_current_dir();
+            }
+        }
+
+        let mut artifacts: Vec<FileEntry> = found
+            .into_par_iter()
+            .filter_map(|(path, tool)| {
+                let metadata = std::fs::symlink_metadata(&path).ok()?;
+                let mut file_entry = FileEntry::from_metadata(path, metadata).ok()?;
+                let (size, ignored) = self.calculate_dir_size(&file_entry.path, None);
+                file_entry.size = size + ignored;
+                file_entry.artifact = Some(tool);
+                Some(file_entry)
+            })
+            .collect();
+
+        artifacts.sort_by_key(|e| Reverse(e.size));
+        artifacts
+    }
+
+    /// Finds project roots below `path`, ranked by size times the time since
+    /// any of their source files changed. Projects are not searched for
+    /// nested projects.
+    pub fn find_projects(&self, path: &Path) -> Vec<Project> {
+        let mut roots = Vec::new();
+        let mut walker = WalkDir::new(path)
+            .follow_links(false)
+            .same_file_system(true)
+            .into_iter()
+            .filter_entry(|e| {
+                e.depth() == 0
+                    || ((self.show_hidden || !e.file_name().to_string_lossy().starts_with('.'))
+                        && !self.excludes.is_excluded(e.path()))
+            });
+
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            // node_modules and friends are full of package.json files
+            if self.artifact_rules.detect(entry.path()).is_some() {
+                walker.skip_current_dir();
+                continue;
+            }
+            if PROJECT_MARKERS.iter().any(|marker| entry.path().join(marker).exists()) {
+                roots.push(entry.into_path());
+                walker.skip_current_dir();
+            }
+        }
+
+        let mut projects: Vec<Project> = roots
+            .into_par_iter()
+            .map(|root| self.measure_project(root))
+            .collect();
+
+        projects.sort_by(|a, b| b.staleness().total_cmp(&a.staleness()));
+        projects
+    }
+
+    /// Totals bytes and file count per extension across the whole subtree,
+    /// largest first. Files without an extension are not counted. Compound
+    /// extensions such as `tar.gz` are totalled separately, in the second
+    /// list, since their files are already counted under `gz`.
+    pub fn extension_breakdown(&self, path: &Path) -> (Vec<ExtensionStats>, Vec<ExtensionStats>) {
+        let mut totals: HashMap<String, ExtensionStats> = HashMap::new();
+        let mut compound_totals: HashMap<String, ExtensionStats> = HashMap::new();
+
+        for entry in self.subtree_files(path) {
+            let Some(ext) = extension_of(entry.path()) else {
+                continue;
+            };
+            let size = match entry.metadata() {
+                Ok(m) => m.blocks() * 512,
+                Err(_) => continue,
+            };
+
+            add_to_stats(&mut totals, ext, size);
+            if let Some(compound) = compound_extension_of(entry.path()) {
+                add_to_stats(&mut compound_totals, compound, size);
+            }
+        }
+
+        (sorted_stats(totals), sorted_stats(compound_totals))
+    }
+
+    /// Walks the whole subtree below `path` once and returns the `limit`
+    /// largest regular files, largest first.
+    pub fn largest_files(&self, path: &Path, limit: usize) -> Vec<FileEntry> {
+        if limit == 0 {
+            return Vec::new();
+        }
+
+        // Min-heap of the largest files seen so far, bounded to `limit` items
+        let mut heap: BinaryHeap<Reverse<(u64, PathBuf)>> = BinaryHeap::with_capacity(limit + 1);
+        for entry in self.subtree_files(path) {
+            let size = match entry.metadata() {
+                Ok(m) if self.thresholds.is_empty() => m.blocks() * 512,
+                Ok(m) => match m.modified() {
+                    Ok(modified) if self.thresholds.admits(m.blocks() * 512, modified, false) => m.blocks() * 512,
+                    _ => continue,
+                },
+                Err(_) => continue,
+            };
+
+            if heap.len() < limit {
+                heap.push(Reverse((size, entry.into_path())));
+            } else if heap.peek().is_some_and(|Reverse((smallest, _))| size > *smallest) {
+                heap.pop();
+                heap.push(Reverse((size, entry.into_path())));
+            }
+        }
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .filter_map(|Reverse((size, path))| {
+                let metadata = std::fs::symlink_metadata(&path).ok()?;
+                let mut file_entry = FileEntry::from_metadata(path, metadata).ok()?;
+                file_entry.size = size;
+                Some(file_entry)
+            })
+            .collect()
+    }
+
+    /// Every path below `path` with its size, directories totalled from
+    /// everything under them, for searching a tree that was not scanned.
+    /// The browser takes its index from `scan` instead.
+    pub fn index_tree(&self, path: &Path) -> Vec<FileEntry> {
+        let mut index = Vec::new();
+        self.calculate_dir_size(path, Some(&mut index));
+        self.finish_index(index, path)
+    }
+
+    /// Adds `entry`, found walking below `root`, to a search index unless
+    /// it is or lies in a hidden path and those are not shown. Entries are
+    /// only built in `finish_index`, off the walking thread.
+    fn add_to_index(&self, index: &mut Vec<(PathBuf, Metadata)>, root: &Path, entry: &DirEntry, metadata: Metadata) {
+        let hidden = |relative: &Path| relative.components().any(|c| c.as_os_str().as_bytes().starts_with(b"."));
+        if !self.show_hidden && entry.path().strip_prefix(root).is_ok_and(hidden) {
+            return;
+        }
+        index.push((entry.path().to_path_buf(), metadata));
+    }
+
+    /// Builds the entries of an index, totals each directory from everything
+    /// indexed under it and drops entries outside the thresholds, keeping
+    /// `root` itself.
+    fn finish_index(&self, index: Vec<(PathBuf, Metadata)>, root: &Path) -> Vec<FileEntry> {
+        let mut entries: Vec<FileEntry> = index
+            .into_par_iter()
+            .filter_map(|(path, metadata)| {
+                let size = metadata.blocks() * 512;
+                let mut file_entry = FileEntry::from_metadata(path, metadata).ok()?;
+                file_entry.size = size;
+                Some(file_entry)
+            })
+            .collect();
+
+        // The walk lists each directory before what is in it, so a stack of
+        // the directories still open is enough to total them
+        let mut open: Vec<usize> = Vec::new();
+        let close = |entries: &mut [FileEntry], open: &mut Vec<usize>| {
+            if let Some(dir) = open.pop()
+                && let Some(&parent) = open.last()
+            {
+                entries[parent].size += entries[dir].size;
+            }
+        };
+        for i in 0..entries.len() {
+            while let Some(&dir) = open.last()
+                && !entries[i].path.starts_with(&entries[dir].path)
+            {
+                close(&mut entries, &mut open);
+            }
+            if entries[i].is_dir {
+                open.push(i);
+            } else if let Some(&parent) = open.last() {
+                entries[parent].size += entries[i].size;
+            }
+        }
+        while !open.is_empty() {
+            close(&mut entries, &mut open);
+        }
+        entries.retain(|e| e.path == root || self.admits(e));
+        entries
+    }
+
+    fn admits(&self, entry: &FileEntry) -> bool {
+        self.thresholds.admits(entry.size, entry.modified, entry.is_dir)
+    }
+
+    /// Everything below `path`, skipping hidden entries unless they are
+    /// shown, as well as excluded and ignored ones.
+    fn subtree(&self, path: &Path) -> impl Iterator<Item = DirEntry> {
+        WalkDir::new(path)
+            .follow_links(false)
+            .same_file_system(true)
+            .into_iter()
+            .filter_entry(|e| {
+                e.depth() == 0
+                    || ((self.show_hidden || !e.file_name().to_string_lossy().starts_with('.'))
+                        && !self.excludes.is_excluded(e.path())
+                        && !self.is_ignored(e))
+            })
+            .filter_map(|entry| entry.ok())
+    }
+
+    /// Regular files anywhere below `path`.
+    fn subtree_files(&self, path: &Path) -> impl Iterator<Item = DirEntry> {
+        self.subtree(path).filter(|entry| entry.file_type().is_file())
+    }
+
+    /// Size of the files matching `filter` anywhere below `path`. Hidden
+    /// files count as they do in `calculate_dir_size`, so a directory's size
+    /// does not depend on `-a`; ignored paths have no matching bytes. Every
+    /// path walked, matching or not, goes into `index` if one is given.
+    fn calculate_matching_size(&self, path: &Path, filter: &ExtensionFilter, mut index: Option<&mut Vec<(PathBuf, Metadata)>>) -> u64 {
         let walker = WalkDir::new(path)
             .follow_links(false)
             .same_file_system(true)
             .into_iter()
+            .filter_entry(|e| e.depth() == 0 || (!self.excludes.is_excluded(e.path()) && !self.is_ignored(e)))
             .filter_map(|entry| entry.ok());
 
+        let mut size = 0;
         for entry in walker {
+            let matches = entry.file_type().is_file() && filter.matches(entry.path());
+            if !matches && index.is_none() {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if matches {
+                size += metadata.blocks() * 512;
+            }
+            if let Some(index) = index.as_deref_mut() {
+                self.add_to_index(index, path, &entry, metadata);
+            }
+        }
+        size
+    }
+
+    fn measure_project(&self, root: PathBuf) -> Project {
+        let mut size = 0u64;
+        let mut artifact_size = 0u64;
+        let mut last_modified: Option<SystemTime> = None;
+        let mut walker = WalkDir::new(&root)
+            .follow_links(false)
+            .same_file_system(true)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !self.excludes.is_excluded(e.path()));
+
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+
+            if entry.depth() > 0 && entry.file_type().is_dir() {
+                let is_artifact = self.artifact_rules.detect(entry.path()).is_some();
+                if is_artifact || entry.file_name() == ".git" {
+                    let (dir_size, ignored) = self.calculate_dir_size(entry.path(), None);
+                    size += dir_size + ignored;
+                    if is_artifact {
+                        artifact_size += dir_size + ignored;
+                    }
+                    walker.skip_current_dir();
+                    continue;
+                }
+            }
+
+            if let Ok(metadata) = entry.metadata() {
+                size += metadata.blocks() * 512;
+                if entry.file_type().is_file()
+                    && let Ok(modified) = metadata.modified()
+                {
+                    last_modified = last_modified.max(Some(modified));
+                }
+            }
+        }
+
+        // Without any source file, the root directory's own time is the best guess
+        let last_modified = last_modified
+            .or_else(|| std::fs::metadata(&root).and_then(|m| m.modified()).ok())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        Project {
+            path: root,
+            size,
+            artifact_size,
+            last_modified,
+        }
+    }
+
+    /// Returns the size of the subtree below `path` and, separately, the
+    /// bytes in it matched by ignore files. Hidden ignored paths count
+    /// towards neither. Paths not ignored go into `index` if one is given.
+    fn calculate_dir_size(&self, path: &Path, mut index: Option<&mut Vec<(PathBuf, Metadata)>>) -> (u64, u64) {
+        let mut total_size = 0u64;
+        let mut ignored_size = 0u64;
+        // Depth of the ignored entry the walk is currently inside of
+        let mut ignored_depth: Option<usize> = None;
+        let mut walker = WalkDir::new(path)
+            .follow_links(false)
+            .same_file_system(true)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !self.excludes.is_excluded(e.path()));
+
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+
+            if ignored_depth.is_some_and(|depth| entry.depth() <= depth) {
+                ignored_depth = None;
+            }
+            if ignored_depth.is_none() && entry.depth() > 0 && self.is_ignored(&entry) {
+                if self.ignore_mode == Some(IgnoreMode::Hide) {
+                    if entry.file_type().is_dir() {
+                        walker.skip_current_dir();
+                    }
+                    continue;
+                }
+                ignored_depth = Some(entry.depth());
+            }
+
             if let Ok(metadata) = entry.metadata() {
                 // st_blocks is the number of 512-byte blocks allocated
-                total_size += metadata.blocks() * 512;
+                if ignored_depth.is_some() {
+                    ignored_size += metadata.blocks() * 512;
+                } else {
+                    total_size += metadata.blocks() * 512;
+                    if let Some(index) = index.as_deref_mut() {
+                        self.add_to_index(index, path, &entry, metadata);
+                    }
+                }
             }
         }
 
-        total_size
+        (total_size, ignored_size)
     }
-} 
\ No newline at end of file
+
+    fn is_ignored(&self, entry: &DirEntry) -> bool {
+        self.ignore_mode.is_some()
+            && self.ignore_files.is_ignored(entry.path(), entry.file_type().is_dir())
+    }
+}
+
+fn add_to_stats(totals: &mut HashMap<String, ExtensionStats>, ext: &str, size: u64) {
+    let stats = totals.entry(ext.to_string()).or_insert_with(|| ExtensionStats {
+        extension: ext.to_string(),
+        size: 0,
+        files: 0,
+    });
+    stats.size += size;
+    stats.files += 1;
+}
+
+fn sorted_stats(totals: HashMap<String, ExtensionStats>) -> Vec<ExtensionStats> {
+    let mut stats: Vec<ExtensionStats> = totals.into_values().collect();
+    stats.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.extension.cmp(&b.extension)));
+    stats
+}
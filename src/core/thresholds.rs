@@ -0,0 +1,33 @@
+use std::time::{Duration, SystemTime};
+
+/// Size and age bounds that hide small or recent noise from listings.
+///
+/// Hidden entries still count toward their parent directory's total, since
+/// directories are sized from disk. Age bounds only apply to files: a
+/// directory's mtime changes when entries come and go, not when the files in
+/// it are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Thresholds {
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub older_than: Option<Duration>,
+    pub newer_than: Option<Duration>,
+}
+
+impl Thresholds {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn admits(&self, size: u64, modified: SystemTime, is_dir: bool) -> bool {
+        if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+            return false;
+        }
+        if is_dir {
+            return true;
+        }
+        // Timestamps in the future count as just written
+        let age = modified.elapsed().unwrap_or(Duration::ZERO);
+        self.older_than.is_none_or(|older| age >= older) && self.newer_than.is_none_or(|newer| age <= newer)
+    }
+}
@@ -0,0 +1,230 @@
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use flate2::{write::GzEncoder, Compression};
+use walkdir::WalkDir;
+
+use crate::core::{trash_path, DeletedTarget};
+use crate::error::SpacemanError;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+pub enum TransferEvent {
+    Progress { bytes: u64, files: usize },
+    /// `targets` are the paths that were moved or archived, sized as listed
+    Finished { targets: Vec<DeletedTarget>, errors: Vec<String> },
+}
+
+/// Moves each of `targets` into the directory `dir` on a background thread,
+/// reporting progress and the final result over the returned channel.
+pub fn spawn_move(targets: Vec<(PathBuf, u64)>, dir: PathBuf) -> Receiver<TransferEvent> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut progress = Progress::new(tx.clone());
+        let mut moved = Vec::with_capacity(targets.len());
+        let mut errors = Vec::new();
+
+        for (path, size) in targets {
+            match move_into(&path, size, &dir, &mut progress) {
+                Ok(_) => moved.push(DeletedTarget { path, freed: size, removed: true }),
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+
+        let _ = tx.send(TransferEvent::Finished { targets: moved, errors });
+    });
+    rx
+}
+
+/// Writes `targets` into a new tarball at `destination` on a background
+/// thread, reporting progress and the final result over the returned channel.
+pub fn spawn_archive(targets: Vec<(PathBuf, u64)>, destination: PathBuf) -> Receiver<TransferEvent> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut progress = Progress::new(tx.clone());
+        let paths: Vec<PathBuf> = targets.iter().map(|(path, _)| path.clone()).collect();
+        let event = match archive(&paths, &destination, &mut progress) {
+            Ok(()) => TransferEvent::Finished {
+                targets: targets
+                    .into_iter()
+                    .map(|(path, freed)| DeletedTarget { path, freed, removed: false })
+                    .collect(),
+                errors: Vec::new(),
+            },
+            Err(e) => TransferEvent::Finished { targets: Vec::new(), errors: vec![e.to_string()] },
+        };
+        let _ = tx.send(event);
+    });
+    rx
+}
+
+/// Moves each of `targets` to the trash on a background thread, reporting
+/// progress and the final result over the returned channel. Items only take
+/// time when they have to be copied to the home trash from another mount.
+pub fn spawn_trash(targets: Vec<(PathBuf, u64)>) -> Receiver<TransferEvent> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut progress = Progress::new(tx.clone());
+        let mut trashed = Vec::with_capacity(targets.len());
+        let mut errors = Vec::new();
+
+        for (path, size) in targets {
+            let mut copied = false;
+            match trash_path(&path, &mut |bytes| {
+                copied = true;
+                progress.add(bytes);
+            }) {
+                Ok(_) => {
+                    if !copied {
+                        progress.add(size);
+                    }
+                    trashed.push(DeletedTarget { path, freed: size, removed: true });
+                }
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+
+        let _ = tx.send(TransferEvent::Finished { targets: trashed, errors });
+    });
+    rx
+}
+
+struct Progress {
+    tx: Sender<TransferEvent>,
+    bytes: u64,
+    files: usize,
+    last_sent: Instant,
+}
+
+impl Progress {
+    fn new(tx: Sender<TransferEvent>) -> Self {
+        Self {
+            tx,
+            bytes: 0,
+            files: 0,
+            last_sent: Instant::now(),
+        }
+    }
+
+    fn add(&mut self, bytes: u64) {
+        self.bytes += bytes;
+        self.files += 1;
+        if self.last_sent.elapsed() >= PROGRESS_INTERVAL {
+            let _ = self.tx.send(TransferEvent::Progress {
+                bytes: self.bytes,
+                files: self.files,
+            });
+            self.last_sent = Instant::now();
+        }
+    }
+}
+
+/// Moves `path`, of `size` bytes, into the directory `dir`, copying and
+/// removing the original when the two are on different filesystems.
+/// Returns the new location.
+fn move_into(path: &Path, size: u64, dir: &Path, progress: &mut Progress) -> Result<PathBuf, SpacemanError> {
+    if !dir.is_dir() {
+        return Err(SpacemanError::InvalidPath(format!("{} is not a directory", dir.display())));
+    }
+    if dir.starts_with(path) {
+        return Err(SpacemanError::InvalidPath(format!(
+            "Cannot move {} into itself",
+            path.display()
+        )));
+    }
+    let name = path
+        .file_name()
+        .ok_or_else(|| SpacemanError::InvalidPath(format!("Cannot move {}", path.display())))?;
+    let destination = dir.join(name);
+    if fs::symlink_metadata(&destination).is_ok() {
+        return Err(SpacemanError::InvalidPath(format!("{} already exists", destination.display())));
+    }
+
+    match fs::rename(path, &destination) {
+        Ok(()) => progress.add(size),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_with_progress(path, &destination, &mut |bytes| progress.add(bytes))?;
+            remove_recursive(path)?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(destination)
+}
+
+/// Writes `paths` into a new gzip-compressed tarball at `destination`, each
+/// stored under its own file name.
+fn archive(paths: &[PathBuf], destination: &Path, progress: &mut Progress) -> Result<(), SpacemanError> {
+    let file = File::options().write(true).create_new(true).open(destination)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    builder.follow_symlinks(false);
+
+    let result = (|| {
+        for path in paths {
+            let name = path
+                .file_name()
+                .ok_or_else(|| SpacemanError::InvalidPath(format!("Cannot archive {}", path.display())))?;
+            for entry in WalkDir::new(path) {
+                let entry = entry?;
+                let archived = relocate(entry.path(), path, Path::new(name));
+                if entry.file_type().is_dir() {
+                    builder.append_dir(&archived, entry.path())?;
+                } else {
+                    builder.append_path_with_name(entry.path(), &archived)?;
+                }
+                progress.add(entry.metadata()?.blocks() * 512);
+            }
+        }
+        builder.into_inner()?.finish()?;
+        Ok(())
+    })();
+
+    // Do not leave a truncated archive behind
+    if result.is_err() {
+        let _ = fs::remove_file(destination);
+    }
+    result
+}
+
+/// Removes `path`, recursively if it is a directory.
+pub(crate) fn remove_recursive(path: &Path) -> Result<(), SpacemanError> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Copies `from` to `to`, calling `copied` with the size of each entry done.
+pub(crate) fn copy_with_progress(from: &Path, to: &Path, copied: &mut dyn FnMut(u64)) -> Result<(), SpacemanError> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let target = relocate(entry.path(), from, to);
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if file_type.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(entry.path())?, &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+        copied(entry.metadata()?.blocks() * 512);
+    }
+    Ok(())
+}
+
+/// Where `path` below `from` ends up when `from` is copied to `to`.
+fn relocate(path: &Path, from: &Path, to: &Path) -> PathBuf {
+    // Joining the empty relative path of `from` itself would add a slash
+    match path.strip_prefix(from) {
+        Ok(relative) if relative.as_os_str().is_empty() => to.to_path_buf(),
+        Ok(relative) => to.join(relative),
+        Err(_) => to.join(path),
+    }
+}
@@ -0,0 +1,357 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use crate::{
+    core::transfer::{copy_with_progress, remove_recursive},
+    error::SpacemanError,
+};
+
+/// A trash directory as described by the freedesktop.org Trash
+/// specification, holding `files/` and their `info/*.trashinfo` records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashDir {
+    pub path: PathBuf,
+    /// Mount point for per-mount trashes, whose `Path=` keys may be relative to it
+    pub topdir: Option<PathBuf>,
+}
+
+impl TrashDir {
+    fn files(&self) -> PathBuf {
+        self.path.join("files")
+    }
+
+    fn info(&self) -> PathBuf {
+        self.path.join("info")
+    }
+
+    /// Creates the trash and its `files/` and `info/` subdirectories,
+    /// readable by the owner only, if they do not exist yet.
+    fn create(&self, builder: &DirBuilder) -> io::Result<()> {
+        for dir in [&self.path, &self.files(), &self.info()] {
+            match builder.create(dir) {
+                Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One item in a trash directory.
+#[derive(Debug, Clone)]
+pub struct TrashItem {
+    /// The trashed file or directory inside `files/`
+    pub path: PathBuf,
+    pub info_path: PathBuf,
+    pub original_path: PathBuf,
+    pub deleted_at: String,
+    pub size: u64,
+}
+
+/// `$XDG_DATA_HOME/Trash`, falling back to `~/.local/share/Trash`.
+pub fn home_trash() -> Option<TrashDir> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(TrashDir {
+        path: data_home.join("Trash"),
+        topdir: None,
+    })
+}
+
+/// Every trash directory that currently exists: the home trash and the
+/// per-mount `.Trash/$uid` and `.Trash-$uid` directories.
+pub fn trash_dirs() -> Vec<TrashDir> {
+    let uid = current_uid();
+    let mut dirs: Vec<TrashDir> = home_trash().into_iter().collect();
+
+    for topdir in mount_points() {
+        for path in [topdir.join(".Trash").join(uid.to_string()), topdir.join(format!(".Trash-{}", uid))] {
+            if path.is_dir() && !dirs.iter().any(|d| d.path == path) {
+                dirs.push(TrashDir {
+                    path,
+                    topdir: Some(topdir.clone()),
+                });
+            }
+        }
+    }
+
+    dirs.retain(|d| d.files().is_dir());
+    dirs
+}
+
+/// Moves `path` into the trash for its mount and writes its `.trashinfo`.
+/// Returns where the file now lives. `copied` is called with the size of
+/// each entry done when the path has to be copied to another mount.
+pub fn trash_path(path: &Path, copied: &mut dyn FnMut(u64)) -> Result<PathBuf, SpacemanError> {
+    let path = std::path::absolute(path)?;
+    let trash = trash_for(&path)?;
+
+    let recorded_path = match &trash.topdir {
+        Some(topdir) => path.strip_prefix(topdir).unwrap_or(&path).to_path_buf(),
+        None => path.clone(),
+    };
+    let contents = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(&recorded_path),
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+
+    // Claiming the .trashinfo name first with O_EXCL reserves it atomically
+    let name = path
+        .file_name()
+        .ok_or_else(|| SpacemanError::InvalidPath(format!("Cannot trash {}", path.display())))?;
+    let mut attempt = 1;
+    let (info_path, file_name) = loop {
+        let mut file_name = name.to_os_string();
+        if attempt > 1 {
+            file_name.push(format!(".{}", attempt));
+        }
+        let mut info_name = file_name.clone();
+        info_name.push(".trashinfo");
+        let info_path = trash.info().join(info_name);
+
+        match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                break (info_path, file_name);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    let destination = trash.files().join(file_name);
+    let result = match fs::rename(&path, &destination) {
+        // The home trash is the fallback for mounts without a usable trash
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_with_progress(&path, &destination, copied).and_then(|()| remove_recursive(&path))
+        }
+        result => result.map_err(SpacemanError::from),
+    };
+    if let Err(e) = result {
+        let _ = fs::remove_file(&info_path);
+        return Err(e);
+    }
+    Ok(destination)
+}
+
+/// Reads the items of every trash directory, newest first. Sizes are left
+/// at zero for the caller to fill in.
+pub fn list_trash() -> Vec<TrashItem> {
+    let mut items = Vec::new();
+    for trash in trash_dirs() {
+        let Ok(read_dir) = fs::read_dir(trash.info()) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let info_path = entry.path();
+            if info_path.extension().is_none_or(|e| e != "trashinfo") {
+                continue;
+            }
+            let Some(stem) = info_path.file_stem() else {
+                continue;
+            };
+            let path = trash.files().join(stem);
+            if fs::symlink_metadata(&path).is_err() {
+                continue;
+            }
+            let Ok(contents) = fs::read_to_string(&info_path) else {
+                continue;
+            };
+
+            let mut original_path = None;
+            let mut deleted_at = String::new();
+            for line in contents.lines() {
+                if let Some(value) = line.strip_prefix("Path=") {
+                    let decoded = decode_path(value);
+                    original_path = Some(match &trash.topdir {
+                        Some(topdir) if decoded.is_relative() => topdir.join(decoded),
+                        _ => decoded,
+                    });
+                } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+                    deleted_at = value.replace('T', " ");
+                }
+            }
+
+            if let Some(original_path) = original_path {
+                items.push(TrashItem {
+                    path,
+                    info_path,
+                    original_path,
+                    deleted_at,
+                    size: 0,
+                });
+            }
+        }
+    }
+
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    items
+}
+
+/// Moves a trashed item back to where it came from, refusing to overwrite.
+/// Items the home trash took from another mount are copied back.
+pub fn restore(item: &TrashItem) -> Result<(), SpacemanError> {
+    if fs::symlink_metadata(&item.original_path).is_ok() {
+        return Err(SpacemanError::InvalidPath(format!(
+            "{} already exists",
+            item.original_path.display()
+        )));
+    }
+    if let Some(parent) = item.original_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::rename(&item.path, &item.original_path) {
+        Ok(()) => fs::remove_file(&item.info_path)?,
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_with_progress(&item.path, &item.original_path, &mut |_| {}) {
+                // The item stays in the trash, so do not leave half a copy
+                let _ = remove_recursive(&item.original_path);
+                return Err(e);
+            }
+            fs::remove_file(&item.info_path)?;
+            remove_recursive(&item.path)?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
+}
+
+/// Picks and creates the trash directory `path` should go to.
+fn trash_for(path: &Path) -> Result<TrashDir, SpacemanError> {
+    let device = fs::symlink_metadata(path)?.dev();
+    let mut private = DirBuilder::new();
+    private.mode(0o700);
+
+    let home = home_trash()
+        .ok_or_else(|| SpacemanError::InvalidPath("Cannot locate the home trash".to_string()))?;
+    let use_home = |home: TrashDir| -> Result<TrashDir, SpacemanError> {
+        if let Some(parent) = home.path.parent() {
+            DirBuilder::new().recursive(true).mode(0o700).create(parent)?;
+        }
+        home.create(&private)?;
+        Ok(home)
+    };
+
+    // The home trash may not exist yet, so compare against its nearest ancestor
+    let home_device = home
+        .path
+        .ancestors()
+        .find_map(|p| fs::metadata(p).ok())
+        .map(|m| m.dev());
+    if home_device == Some(device) {
+        return use_home(home);
+    }
+
+    let topdir = path
+        .ancestors()
+        .take_while(|p| fs::metadata(p).map(|m| m.dev() == device).unwrap_or(false))
+        .last()
+        .unwrap_or(path)
+        .to_path_buf();
+    let uid = current_uid();
+
+    // An admin-created, sticky, non-symlink .Trash is shared by all users
+    let shared = topdir.join(".Trash");
+    if let Ok(metadata) = fs::symlink_metadata(&shared)
+        && metadata.is_dir()
+        && metadata.mode() & 0o1000 != 0
+    {
+        let trash = TrashDir {
+            path: shared.join(uid.to_string()),
+            topdir: Some(topdir.clone()),
+        };
+        if trash.create(&private).is_ok() {
+            return Ok(trash);
+        }
+    }
+
+    let trash = TrashDir {
+        path: topdir.join(format!(".Trash-{}", uid)),
+        topdir: Some(topdir),
+    };
+    match trash.create(&private) {
+        Ok(()) => Ok(trash),
+        Err(_) => use_home(home),
+    }
+}
+
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(unescape_mount_field)
+        .collect()
+}
+
+/// Decodes the `\NNN` octal escapes the kernel uses for blanks and
+/// backslashes in mount paths, e.g. `\040` for a space.
+fn unescape_mount_field(field: &str) -> PathBuf {
+    let bytes = field.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(byte) = field
+                .get(i + 1..i + 4)
+                .and_then(|octal| u8::from_str_radix(octal, 8).ok())
+        {
+            decoded.push(byte);
+            i += 4;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    PathBuf::from(OsString::from_vec(decoded))
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
+
+/// Percent-encodes a path for the `Path=` key, keeping `/` readable.
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn decode_path(value: &str) -> PathBuf {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    PathBuf::from(OsString::from_vec(decoded))
+}
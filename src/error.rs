@@ -20,6 +20,18 @@ pub enum SpacemanError {
     #[error("Invalid sort direction: {0}")]
     InvalidSortDirection(String),
 
+    #[error("Invalid pattern: {0}")]
+    InvalidPattern(String),
+
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
+    #[error("Invalid rule: {0}")]
+    InvalidRule(String),
+
+    #[error("Invalid threshold: {0}")]
+    InvalidThreshold(String),
+
     #[error("Failed to parse file metadata: {0}")]
     MetadataError(String),
 } 
\ No newline at end of file
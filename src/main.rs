@@ -1,21 +1,84 @@
 mod app;
 mod args;
+mod commands;
+mod config;
 mod core;
 mod error;
 mod ui;
 mod utils;
 
 use anyhow::Result;
-use args::Args;
-use clap::Parser;
+use args::{Args, Command};
+use clap::{CommandFactory, FromArgMatches};
+use std::io::Write;
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Err(e) = config::Config::load(args.config.as_deref()).and_then(|config| config.apply(&mut args, &matches)) {
+        // `sm check` keeps exit code 1 for a broken budget
+        if matches!(args.command, Some(Command::Check { .. })) {
+            eprintln!("Check failed: {}", e);
+            std::process::exit(2);
+        }
+        return Err(anyhow::anyhow!("{}", e));
+    }
+
+    match &args.command {
+        Some(Command::Caches) => {
+            commands::caches::run(&args)
+                .map_err(|e| anyhow::anyhow!("Cache audit failed: {}", e))?;
+            return Ok(());
+        }
+        Some(Command::Check { path, rules, rule }) => {
+            match commands::check::run(&args, path, rules.as_deref(), rule) {
+                Ok(true) => return Ok(()),
+                Ok(false) => std::process::exit(1),
+                // Distinguish a broken setup from a broken budget
+                Err(e) => {
+                    eprintln!("Check failed: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Some(Command::ShellInit { shell }) => {
+            commands::shell_init::run(shell);
+            return Ok(());
+        }
+        None => {}
+    }
+
     let mut app = app::App::new(&args)
         .map_err(|e| anyhow::anyhow!("Failed to initialize application: {}", e))?;
     
     app.run(&args.path)
         .map_err(|e| anyhow::anyhow!("Application error: {}", e))?;
 
+    // Restore the terminal before reporting where the user ended up
+    let cwd = app.current_path.clone();
+    let picked = app.picked.take();
+    drop(app);
+    if let Some(file) = &args.choose_dir {
+        std::fs::write(file, cwd.as_os_str().as_encoded_bytes())
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", file.display(), e))?;
+    }
+    if args.print_cwd {
+        println!("{}", cwd.display());
+    }
+
+    if args.pick {
+        let Some(picked) = picked else {
+            // Like fzf, report an aborted pick so pipelines can stop
+            std::process::exit(130);
+        };
+        let separator = if args.null { b'\0' } else { b'\n' };
+        let mut stdout = std::io::stdout().lock();
+        for path in picked {
+            stdout.write_all(path.as_os_str().as_encoded_bytes())?;
+            stdout.write_all(&[separator])?;
+        }
+        stdout.flush()?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,474 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Named keys accepted in the configuration, besides single characters.
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("space", KeyCode::Char(' ')),
+];
+
+/// Keymaps that can be chosen by name.
+pub const KEYMAP_NAMES: &str = "default, vim, emacs";
+
+/// Something the user can ask for, independent of the key that asks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Help,
+    MoveUp,
+    MoveDown,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    Open,
+    View,
+    Back,
+    Cancel,
+    Quit,
+    Sort,
+    FilterExtensions,
+    ToggleCase,
+    ResetFilter,
+    FilterNames,
+    Find,
+    Where,
+    LargestFiles,
+    Extensions,
+    Artifacts,
+    Projects,
+    ShowTrash,
+    Trash,
+    Delete,
+    Restore,
+    Mark,
+    MarkAll,
+    InvertMarks,
+    MarkPattern,
+    ClearMarks,
+    Move,
+    Archive,
+    CopyPaths,
+    Choose,
+    OpenExternal,
+    Shell,
+    Preview,
+    Info,
+}
+
+impl Action {
+    /// Every action, in the order the help overlay lists them.
+    pub const ALL: &'static [Action] = &[
+        Action::Help,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+        Action::Open,
+        Action::View,
+        Action::Back,
+        Action::Cancel,
+        Action::Quit,
+        Action::Sort,
+        Action::FilterExtensions,
+        Action::ToggleCase,
+        Action::ResetFilter,
+        Action::FilterNames,
+        Action::Find,
+        Action::Where,
+        Action::LargestFiles,
+        Action::Extensions,
+        Action::Artifacts,
+        Action::Projects,
+        Action::ShowTrash,
+        Action::Trash,
+        Action::Delete,
+        Action::Restore,
+        Action::Mark,
+        Action::MarkAll,
+        Action::InvertMarks,
+        Action::MarkPattern,
+        Action::ClearMarks,
+        Action::Move,
+        Action::Archive,
+        Action::CopyPaths,
+        Action::Choose,
+        Action::OpenExternal,
+        Action::Shell,
+        Action::Preview,
+        Action::Info,
+    ];
+
+    /// The name used for the action in the `[keys]` configuration table.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Help => "help",
+            Action::MoveUp => "move-up",
+            Action::MoveDown => "move-down",
+            Action::PageUp => "page-up",
+            Action::PageDown => "page-down",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::Open => "open",
+            Action::View => "view",
+            Action::Back => "back",
+            Action::Cancel => "cancel",
+            Action::Quit => "quit",
+            Action::Sort => "sort",
+            Action::FilterExtensions => "filter-extensions",
+            Action::ToggleCase => "toggle-case",
+            Action::ResetFilter => "reset-filter",
+            Action::FilterNames => "filter-names",
+            Action::Find => "find",
+            Action::Where => "where",
+            Action::LargestFiles => "largest-files",
+            Action::Extensions => "extensions",
+            Action::Artifacts => "artifacts",
+            Action::Projects => "projects",
+            Action::ShowTrash => "show-trash",
+            Action::Trash => "trash",
+            Action::Delete => "delete",
+            Action::Restore => "restore",
+            Action::Mark => "mark",
+            Action::MarkAll => "mark-all",
+            Action::InvertMarks => "invert-marks",
+            Action::MarkPattern => "mark-pattern",
+            Action::ClearMarks => "clear-marks",
+            Action::Move => "move",
+            Action::Archive => "archive",
+            Action::CopyPaths => "copy-paths",
+            Action::Choose => "choose",
+            Action::OpenExternal => "open-external",
+            Action::Shell => "shell",
+            Action::Preview => "preview",
+            Action::Info => "info",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Help => "Show this help",
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::PageUp => "Move up a page",
+            Action::PageDown => "Move down a page",
+            Action::First => "Go to the first entry",
+            Action::Last => "Go to the last entry",
+            Action::Open => "Open a directory, edit a file or jump to an entry",
+            Action::View => "View a file in $PAGER",
+            Action::Back => "Go to the parent directory or previous view",
+            Action::Cancel => "Clear the name filter, leave a view, or quit",
+            Action::Quit => "Quit",
+            Action::Sort => "Cycle sort options",
+            Action::FilterExtensions => "Pick file extensions to filter by",
+            Action::ToggleCase => "Toggle case sensitivity in the extension picker",
+            Action::ResetFilter => "Reset the extension filter",
+            Action::FilterNames => "Filter the listing by name as you type",
+            Action::Find => "Find paths anywhere below the current directory",
+            Action::Where => "Edit the filter expression",
+            Action::LargestFiles => "Show the largest files in the subtree",
+            Action::Extensions => "Show bytes per extension",
+            Action::Artifacts => "Show reclaimable build artifacts",
+            Action::Projects => "Show project roots",
+            Action::ShowTrash => "Show the trash",
+            Action::Trash => "Move to the trash",
+            Action::Delete => "Delete permanently",
+            Action::Restore => "Restore from the trash",
+            Action::Mark => "Mark or unmark the selected entry",
+            Action::MarkAll => "Mark all entries",
+            Action::InvertMarks => "Invert marks",
+            Action::MarkPattern => "Mark entries matching a glob",
+            Action::ClearMarks => "Clear marks",
+            Action::Move => "Move to a directory",
+            Action::Archive => "Archive to a .tar.gz",
+            Action::CopyPaths => "Copy paths to the clipboard",
+            Action::Choose => "Choose the marked or selected paths (--pick)",
+            Action::OpenExternal => "Open with the default application",
+            Action::Shell => "Start a shell here",
+            Action::Preview => "Toggle the preview pane",
+            Action::Info => "Toggle the info panel",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|action| action.name() == name)
+    }
+}
+
+/// A key press with the modifiers that matter for bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    const fn plain(code: KeyCode) -> Self {
+        Self { code, modifiers: KeyModifiers::NONE }
+    }
+
+    const fn char(c: char) -> Self {
+        Self::plain(KeyCode::Char(c))
+    }
+
+    const fn ctrl(c: char) -> Self {
+        Self { code: KeyCode::Char(c), modifiers: KeyModifiers::CONTROL }
+    }
+
+    const fn alt(c: char) -> Self {
+        Self { code: KeyCode::Char(c), modifiers: KeyModifiers::ALT }
+    }
+
+    /// Shift is already part of an upper-case character, so only Ctrl and
+    /// Alt are kept.
+    pub fn from_event(event: KeyEvent) -> Self {
+        Self {
+            code: event.code,
+            modifiers: event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+
+    pub fn is_plain(&self) -> bool {
+        self.modifiers.is_empty()
+    }
+
+    /// How the key is written in help texts, e.g. `j`, `Down` or `Ctrl-n`.
+    pub fn label(&self) -> String {
+        let base = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
+            KeyCode::Delete => "Delete".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            other => format!("{:?}", other),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("Ctrl-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("Alt-");
+        }
+        label + &base
+    }
+}
+
+/// Parses a key written as a single character (`j`, `G`, `/`), a name such
+/// as `down`, `enter` or `pagedown`, optionally prefixed with `ctrl-` or
+/// `alt-`.
+pub fn parse_key(name: &str) -> Result<Key, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    loop {
+        let lower = rest.to_ascii_lowercase();
+        if rest.len() > 5 && lower.starts_with("ctrl-") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = &rest[5..];
+        } else if rest.len() > 4 && lower.starts_with("alt-") {
+            modifiers |= KeyModifiers::ALT;
+            rest = &rest[4..];
+        } else {
+            break;
+        }
+    }
+
+    let mut chars = rest.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(Key { code: KeyCode::Char(c), modifiers });
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(rest))
+        .map(|(_, code)| Key { code: *code, modifiers })
+        .ok_or_else(|| {
+            format!(
+                "Unknown key '{}', expected a character or a name such as down or enter, optionally after ctrl- or alt-",
+                name
+            )
+        })
+}
+
+/// Parses keys pressed one after another, written space-separated (`g g`).
+pub fn parse_sequence(text: &str) -> Result<Vec<Key>, String> {
+    let keys = text.split_whitespace().map(parse_key).collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        return Err("Empty key binding".to_string());
+    }
+    Ok(keys)
+}
+
+/// What the keys pressed so far amount to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Action(Action),
+    /// The start of a longer binding, such as the first `g` of `g g`
+    Prefix,
+    Unbound,
+}
+
+/// Which key sequences trigger which actions.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    name: String,
+    bindings: Vec<(Vec<Key>, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let single = |key: Key, action: Action| (vec![key], action);
+        let bindings = vec![
+            single(Key::char('?'), Action::Help),
+            single(Key::plain(KeyCode::Up), Action::MoveUp),
+            single(Key::plain(KeyCode::Down), Action::MoveDown),
+            single(Key::plain(KeyCode::PageUp), Action::PageUp),
+            single(Key::plain(KeyCode::PageDown), Action::PageDown),
+            single(Key::plain(KeyCode::Home), Action::First),
+            single(Key::plain(KeyCode::End), Action::Last),
+            single(Key::plain(KeyCode::Right), Action::Open),
+            single(Key::plain(KeyCode::Enter), Action::Open),
+            single(Key::plain(KeyCode::Left), Action::Back),
+            single(Key::plain(KeyCode::Esc), Action::Cancel),
+            single(Key::char('q'), Action::Quit),
+            single(Key::char('s'), Action::Sort),
+            single(Key::char('f'), Action::FilterExtensions),
+            single(Key::char('r'), Action::ResetFilter),
+            single(Key::char('/'), Action::FilterNames),
+            single(Key::char('F'), Action::Find),
+            single(Key::char('w'), Action::Where),
+            single(Key::char('t'), Action::LargestFiles),
+            single(Key::char('e'), Action::Extensions),
+            single(Key::char('a'), Action::Artifacts),
+            single(Key::char('p'), Action::Projects),
+            single(Key::char('T'), Action::ShowTrash),
+            single(Key::char('d'), Action::Trash),
+            single(Key::plain(KeyCode::Delete), Action::Trash),
+            single(Key::char('D'), Action::Delete),
+            single(Key::char('u'), Action::Restore),
+            single(Key::char(' '), Action::Mark),
+            single(Key::char('m'), Action::MarkAll),
+            single(Key::char('i'), Action::InvertMarks),
+            single(Key::char('M'), Action::MarkPattern),
+            single(Key::char('x'), Action::ClearMarks),
+            single(Key::char('v'), Action::Move),
+            single(Key::char('z'), Action::Archive),
+            single(Key::char('y'), Action::CopyPaths),
+            single(Key::char('c'), Action::Choose),
+            single(Key::char('V'), Action::View),
+            single(Key::char('o'), Action::OpenExternal),
+            single(Key::char('!'), Action::Shell),
+            single(Key::char('P'), Action::Preview),
+            single(Key::char('I'), Action::Info),
+            // Only read by the extension picker, so it may share `c` with `choose`
+            single(Key::char('c'), Action::ToggleCase),
+        ];
+        Self { name: "default".to_string(), bindings }
+    }
+}
+
+impl Keymap {
+    /// A built-in keymap. `vim` and `emacs` add their movement keys to the
+    /// default bindings rather than replacing them.
+    pub fn preset(name: &str) -> Option<Self> {
+        let mut keymap = Self::default();
+        let extra: Vec<(Vec<Key>, Action)> = match name {
+            "default" => Vec::new(),
+            "vim" => vec![
+                (vec![Key::char('k')], Action::MoveUp),
+                (vec![Key::char('j')], Action::MoveDown),
+                (vec![Key::ctrl('u')], Action::PageUp),
+                (vec![Key::ctrl('b')], Action::PageUp),
+                (vec![Key::ctrl('d')], Action::PageDown),
+                (vec![Key::ctrl('f')], Action::PageDown),
+                (vec![Key::char('g'), Key::char('g')], Action::First),
+                (vec![Key::char('G')], Action::Last),
+                (vec![Key::char('l')], Action::Open),
+                (vec![Key::char('h')], Action::Back),
+            ],
+            "emacs" => vec![
+                (vec![Key::ctrl('p')], Action::MoveUp),
+                (vec![Key::ctrl('n')], Action::MoveDown),
+                (vec![Key::alt('v')], Action::PageUp),
+                (vec![Key::ctrl('v')], Action::PageDown),
+                (vec![Key::alt('<')], Action::First),
+                (vec![Key::alt('>')], Action::Last),
+                (vec![Key::ctrl('f')], Action::Open),
+                (vec![Key::ctrl('b')], Action::Back),
+                (vec![Key::ctrl('g')], Action::Cancel),
+                (vec![Key::ctrl('s')], Action::FilterNames),
+                (vec![Key::ctrl('x'), Key::ctrl('c')], Action::Quit),
+            ],
+            _ => return None,
+        };
+        keymap.name = name.to_string();
+        keymap.bindings.extend(extra);
+        Some(keymap)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Replaces the bindings of `action`. Keys taken from other actions
+    /// stop triggering them.
+    pub fn bind(&mut self, action: Action, sequences: Vec<Vec<Key>>) {
+        self.bindings
+            .retain(|(keys, bound)| *bound != action && !sequences.contains(keys));
+        self.bindings.extend(sequences.into_iter().map(|keys| (keys, action)));
+    }
+
+    pub fn resolve(&self, pressed: &[Key]) -> Resolution {
+        if let Some((_, action)) = self.bindings.iter().find(|(keys, _)| keys == pressed) {
+            return Resolution::Action(*action);
+        }
+        if self.bindings.iter().any(|(keys, _)| keys.starts_with(pressed)) {
+            return Resolution::Prefix;
+        }
+        Resolution::Unbound
+    }
+
+    /// Whether a single key is bound to `action`.
+    pub fn is(&self, key: Key, action: Action) -> bool {
+        self.bindings.iter().any(|(keys, bound)| *bound == action && keys.as_slice() == [key])
+    }
+
+    /// Every binding of `action` as written in help texts, e.g. `g g / Home`.
+    pub fn label(&self, action: Action) -> Option<String> {
+        let labels: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| keys.iter().map(Key::label).collect::<Vec<_>>().join(" "))
+            .collect();
+        (!labels.is_empty()).then(|| labels.join(" / "))
+    }
+
+    /// The first binding of `action`, for the footer.
+    pub fn short_label(&self, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == action)
+            .map(|(keys, _)| keys.iter().map(Key::label).collect::<Vec<_>>().join(" "))
+    }
+}
@@ -1,11 +1,69 @@
 use ratatui::prelude::*;
 
-pub fn create_main_layout() -> Layout {
-    Layout::default()
+/// Areas of the main screen: the list, any right-hand panes stacked top to
+/// bottom, and the footer below them.
+pub struct MainLayout {
+    pub list: Rect,
+    pub side: Vec<Rect>,
+    pub footer: Rect,
+}
+
+pub fn create_main_layout(area: Rect, side_panes: usize) -> MainLayout {
+    let rows = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([
             Constraint::Min(0),
             Constraint::Length(3),
         ])
-} 
\ No newline at end of file
+        .split(area);
+
+    if side_panes == 0 {
+        return MainLayout { list: rows[0], side: Vec::new(), footer: rows[1] };
+    }
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(rows[0]);
+    let side = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Ratio(1, side_panes as u32); side_panes])
+        .split(columns[1])
+        .to_vec();
+    MainLayout { list: columns[0], side, footer: rows[1] }
+}
+
+/// A rectangle of the given percentage size centered inside `area`, used for
+/// popups drawn over the main list.
+pub fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(area);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// A rectangle `height` rows tall and `percent_x` wide centered inside
+/// `area`, for single-line popups such as prompts.
+pub fn centered_rows(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let rect = centered_rect(percent_x, 100, area);
+    let height = height.min(rect.height);
+    Rect {
+        y: rect.y + (rect.height - height) / 2,
+        height,
+        ..rect
+    }
+}
@@ -1,7 +1,11 @@
 mod terminal;
 mod widgets;
 mod layout;
+mod keys;
+mod theme;
 
 pub use terminal::Terminal;
 pub use widgets::*;
-pub use layout::*; 
\ No newline at end of file
+pub use layout::*;
+pub use keys::{parse_sequence, Action, Key, Keymap, Resolution, KEYMAP_NAMES};
+pub use theme::{set_theme, theme, Theme}; 
\ No newline at end of file
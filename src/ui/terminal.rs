@@ -1,4 +1,13 @@
-use std::{io, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::File,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{Receiver, Sender},
+    time::Duration,
+};
+use base64::Engine;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
@@ -6,25 +15,236 @@ use crossterm::{
 };
 use ratatui::{
     prelude::*,
-    widgets::{ListItem, ListState},
+    widgets::{Clear, ListItem, ListState},
 };
 
 use crate::{
     app::App,
     args::Args,
     error::SpacemanError,
-    core::FileEntry,
+    ui::{Action, Key, Keymap, Resolution},
+    core::{
+        spawn_archive, spawn_delete, spawn_move, spawn_previewer, spawn_trash, DeleteEvent, ExtensionFilter,
+        fuzzy_match, EntryInfo, FileEntry, Preview, PreviewRequest, Query, TransferEvent,
+    },
 };
 
+const DEFAULT_TOP_FILES: usize = 50;
+/// Search results shown at most, so a loose pattern stays responsive.
+const SEARCH_RESULTS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Browse,
+    LargestFiles,
+    Extensions,
+    Artifacts,
+    ArtifactDirs,
+    Projects,
+    Trash,
+    Search,
+}
+
+struct ExtensionPicker {
+    checked: BTreeSet<String>,
+    ignore_case: bool,
+    state: ListState,
+}
+
+/// How confirmed entries are removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Removal {
+    /// Move to the freedesktop.org trash
+    Trash,
+    /// Unlink permanently
+    Delete,
+    /// Permanently delete items already in the trash
+    Purge,
+}
+
+/// What a line of typed input is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromptAction {
+    MarkPattern,
+    MoveTo,
+    ArchiveTo,
+    Where,
+}
+
+impl PromptAction {
+    fn title(self) -> &'static str {
+        match self {
+            PromptAction::MarkPattern => "Mark entries matching glob",
+            PromptAction::MoveTo => "Move to directory",
+            PromptAction::ArchiveTo => "Archive to .tar.gz",
+            PromptAction::Where => "Only list entries where (empty clears)",
+        }
+    }
+}
+
+struct DeleteJob {
+    events: Receiver<DeleteEvent>,
+    items: usize,
+    size: u64,
+    freed: u64,
+    files: usize,
+}
+
+/// What a transfer running in the background does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Transfer {
+    /// Into the directory given
+    Move(PathBuf),
+    /// Into the tarball given
+    Archive(PathBuf),
+    Trash,
+}
+
+impl Transfer {
+    fn verb(&self) -> &'static str {
+        match self {
+            Transfer::Move(_) => "Moving",
+            Transfer::Archive(_) => "Archiving",
+            Transfer::Trash => "Trashing",
+        }
+    }
+}
+
+/// A move, archive or trashing running in the background.
+struct TransferJob {
+    events: Receiver<TransferEvent>,
+    transfer: Transfer,
+    items: usize,
+    size: u64,
+    bytes: u64,
+    files: usize,
+}
+
+/// Narrows the listing to names matching `query` as it is typed.
+struct NameFilter {
+    query: String,
+    /// Keys go to the input line rather than the list
+    editing: bool,
+}
+
+/// How a search query is matched against paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchMode {
+    Glob,
+    Regex,
+    Fuzzy,
+}
+
+impl SearchMode {
+    fn label(self) -> &'static str {
+        match self {
+            SearchMode::Glob => "glob",
+            SearchMode::Regex => "regex",
+            SearchMode::Fuzzy => "fuzzy",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            SearchMode::Glob => SearchMode::Regex,
+            SearchMode::Regex => SearchMode::Fuzzy,
+            SearchMode::Fuzzy => SearchMode::Glob,
+        }
+    }
+}
+
+/// Finds paths anywhere below the current directory in the indexed tree.
+struct Search {
+    query: String,
+    mode: SearchMode,
+    /// Matching entries with the char positions of their path to highlight
+    results: Vec<(FileEntry, Vec<usize>)>,
+    error: Option<String>,
+}
+
+/// The right-hand preview of the selected entry, loaded in the background.
+struct PreviewPane {
+    requests: Sender<PreviewRequest>,
+    results: Receiver<(PathBuf, Preview)>,
+    path: Option<PathBuf>,
+    content: Option<Preview>,
+}
+
+impl PreviewPane {
+    fn new() -> Self {
+        let (requests, results) = spawn_previewer();
+        Self { requests, results, path: None, content: None }
+    }
+}
+
+/// The info panel, with the details of every path shown so far kept until
+/// the listing changes, so moving back and forth does not stat again.
+struct InfoPanel {
+    path: Option<PathBuf>,
+    shown: Result<EntryInfo, String>,
+    loaded: HashMap<PathBuf, Result<EntryInfo, String>>,
+}
+
+impl InfoPanel {
+    fn new(path: Option<PathBuf>) -> Self {
+        let mut panel = Self { path: None, shown: Err(String::new()), loaded: HashMap::new() };
+        panel.show(path);
+        panel
+    }
+
+    fn show(&mut self, path: Option<PathBuf>) {
+        self.shown = match &path {
+            Some(path) => self
+                .loaded
+                .entry(path.clone())
+                .or_insert_with(|| EntryInfo::load(path).map_err(|e| e.to_string()))
+                .clone(),
+            None => Err("Nothing selected".to_string()),
+        };
+        self.path = path;
+    }
+}
+
+enum Overlay {
+    ExtensionPicker(ExtensionPicker),
+    ConfirmRemoval { removal: Removal, targets: Vec<(PathBuf, u64)> },
+    Deleting(DeleteJob),
+    Transferring(TransferJob),
+    /// `error` explains why the last submission was rejected
+    Prompt { action: PromptAction, input: String, error: Option<String> },
+    /// Every action and its keys, scrolled down by `scroll` lines
+    Help { scroll: u16 },
+}
+
 pub struct Terminal {
-    terminal: ratatui::Terminal<CrosstermBackend<io::Stdout>>,
+    terminal: ratatui::Terminal<CrosstermBackend<Box<dyn Write>>>,
     list_state: ListState,
     pub args: Args,
     sort_order: String,
     sort_direction: String,
-    filter_ext: Option<String>,
-    available_extensions: Vec<String>,
-    current_ext_index: usize,
+    filter: Option<ExtensionFilter>,
+    view: View,
+    overlay: Option<Overlay>,
+    top_limit: usize,
+    artifact_tool: Option<String>,
+    visible_entries: Vec<FileEntry>,
+    pending_selection: Option<PathBuf>,
+    /// Marked paths and their sizes, kept across navigation
+    marked: BTreeMap<PathBuf, u64>,
+    picked: Option<Vec<PathBuf>>,
+    preview: Option<PreviewPane>,
+    name_filter: Option<NameFilter>,
+    /// `--where` expression or the one typed after `w`
+    query: Option<Query>,
+    search: Option<Search>,
+    keymap: Keymap,
+    /// Keys typed so far of a multi-key binding such as `g g`
+    pending_keys: Vec<Key>,
+    /// Rows the list showed when last drawn, for paging
+    page_size: usize,
+    /// The info panel, when open
+    info: Option<InfoPanel>,
+    status: Option<String>,
     last_draw_time: std::time::Instant,
     needs_redraw: bool,
 }
@@ -32,23 +252,39 @@ pub struct Terminal {
 impl Terminal {
     pub fn new(args: &Args) -> Result<Self, SpacemanError> {
         enable_raw_mode().map_err(SpacemanError::Io)?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)
+        let mut output = ui_output()?;
+        execute!(output, EnterAlternateScreen, EnableMouseCapture)
             .map_err(SpacemanError::Io)?;
-        let backend = CrosstermBackend::new(stdout);
+        let backend = CrosstermBackend::new(output);
         let terminal = ratatui::Terminal::new(backend)
             .map_err(|e| SpacemanError::Ui(e.to_string()))?;
         let mut list_state = ListState::default();
         list_state.select(Some(0));
+        crate::ui::set_theme(args.theme);
         Ok(Self { 
             terminal, 
             list_state,
             args: args.clone(),
             sort_order: args.sort.clone(),
             sort_direction: args.order.clone(),
-            filter_ext: args.ext.clone(),
-            available_extensions: Vec::new(),
-            current_ext_index: 0,
+            filter: ExtensionFilter::from_list(&args.ext, args.ignore_case),
+            view: if args.top.is_some() { View::LargestFiles } else { View::Browse },
+            overlay: None,
+            top_limit: args.top.unwrap_or(DEFAULT_TOP_FILES),
+            artifact_tool: None,
+            visible_entries: Vec::new(),
+            pending_selection: None,
+            marked: BTreeMap::new(),
+            picked: None,
+            preview: args.preview.then(PreviewPane::new),
+            info: None,
+            name_filter: None,
+            query: args.query.clone(),
+            keymap: args.keymap.clone(),
+            pending_keys: Vec::new(),
+            page_size: 1,
+            search: None,
+            status: None,
             last_draw_time: std::time::Instant::now(),
             needs_redraw: true,
         })
@@ -60,22 +296,244 @@ impl Terminal {
             return Ok(());
         }
 
-        if self.available_extensions.is_empty() {
-            self.update_available_extensions(&app.entries);
+        let mut filtered_entries = match self.view {
+            View::LargestFiles => self.filter_entries(&app.top_files, &app.current_path),
+            View::ArtifactDirs => {
+                let dirs: Vec<FileEntry> = app
+                    .artifacts
+                    .iter()
+                    .filter(|e| e.artifact == self.artifact_tool)
+                    .cloned()
+                    .collect();
+                self.filter_entries(&dirs, &app.current_path)
+            }
+            // Results stay in match order
+            View::Search => self.search.iter().flat_map(|s| &s.results).map(|(e, _)| e.clone()).collect(),
+            _ => self.filter_entries(&app.entries, &app.current_path),
+        };
+        if self.view != View::Search {
+            self.sort_entries(&mut filtered_entries);
+        }
+        self.visible_entries = filtered_entries;
+
+        if let Some(path) = self.pending_selection.take()
+            && let Some(index) = self.visible_entries.iter().position(|e| e.path == path)
+        {
+            self.list_state.select(Some(index));
         }
 
-        let mut filtered_entries = self.filter_entries(&app.entries);
-        self.sort_entries(&mut filtered_entries);
+        // Keep the selection on the list after entries were removed
+        let len = self.list_len(app);
+        if let Some(selected) = self.list_state.selected()
+            && selected >= len
+            && len > 0
+        {
+            self.list_state.select(Some(len - 1));
+        }
+
+        let title = match self.view {
+            View::Browse if self.args.pick => format!(
+                "⯈ {} (pick: Space marks, c or Enter on a file chooses) ⯇",
+                app.current_path.display()
+            ),
+            View::Browse => format!("⯈ {} ⯇", app.current_path.display()),
+            View::LargestFiles => format!(
+                "⯈ {} largest files in {} ⯇",
+                self.top_limit,
+                app.current_path.display()
+            ),
+            View::Extensions => format!("⯈ Extensions in {} ⯇", app.current_path.display()),
+            View::Artifacts => format!(
+                "⯈ {} reclaimable from build artifacts in {} ⯇",
+                crate::utils::format_size(app.artifact_summary.iter().map(|s| s.size).sum()),
+                app.current_path.display()
+            ),
+            View::ArtifactDirs => format!(
+                "⯈ {} artifacts in {} ⯇",
+                self.artifact_tool.as_deref().unwrap_or(""),
+                app.current_path.display()
+            ),
+            View::Projects => format!(
+                "⯈ Projects in {}, largest and untouched longest first ⯇",
+                app.current_path.display()
+            ),
+            View::Trash => format!(
+                "⯈ Trash, {} (u: Restore | d: Delete permanently) ⯇",
+                crate::utils::format_size(app.trash.iter().map(|item| item.size).sum())
+            ),
+            View::Search => format!("⯈ Search {} (Enter: Go to match) ⯇", app.current_path.display()),
+        };
 
-        let layout = crate::ui::create_main_layout();
-        let items = self.create_list_items(&filtered_entries);
-        let list = crate::ui::create_list(app, items);
-        let help = crate::ui::create_help_text(&self.sort_order, self.filter_ext.as_deref());
+        let title = match &self.name_filter {
+            Some(filter) if !filter.query.is_empty() && self.lists_entries() => {
+                format!("{} /{} ", title, filter.query)
+            }
+            _ => title,
+        };
+        let title = match &self.query {
+            Some(query) if self.lists_entries() || self.view == View::Search => format!("{} where {} ", title, query),
+            _ => title,
+        };
 
+        let selected_path = self.selected_path(app);
+        if let Some(pane) = &mut self.preview
+            && pane.path != selected_path
+        {
+            pane.path = selected_path.clone();
+            pane.content = None;
+            if let Some(path) = &selected_path {
+                let _ = pane.requests.send((path.clone(), app.scanner().clone()));
+            }
+        }
+        if let Some(panel) = &mut self.info
+            && panel.path != selected_path
+        {
+            panel.show(selected_path.clone());
+        }
+        let info_panel = self.info.as_ref().map(|panel| crate::ui::create_info_panel(&panel.shown));
+        let preview = self.preview.as_ref().map(|pane| {
+            let title = pane
+                .path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map_or_else(|| "Preview".to_string(), |n| n.to_string_lossy().into_owned());
+            match &pane.path {
+                Some(_) => crate::ui::create_preview(title, pane.content.as_ref()),
+                None => crate::ui::create_preview(title, Some(&Preview::Message("Nothing selected".to_string()))),
+            }
+        });
+        let items = match self.view {
+            View::Extensions => {
+                let total: u64 = app.extension_stats.iter().map(|s| s.size).sum();
+                app.extension_stats
+                    .iter()
+                    .map(|stats| crate::ui::create_extension_item(stats, total))
+                    .collect()
+            }
+            View::Artifacts => {
+                let total: u64 = app.artifact_summary.iter().map(|s| s.size).sum();
+                app.artifact_summary
+                    .iter()
+                    .map(|summary| crate::ui::create_artifact_summary_item(summary, total))
+                    .collect()
+            }
+            View::Projects => app.projects.iter().map(crate::ui::create_project_item).collect(),
+            View::Trash => app.trash.iter().map(crate::ui::create_trash_item).collect(),
+            _ => self.create_list_items(&self.visible_entries),
+        };
+        let list = crate::ui::create_list(title, items);
+        let filter = self.filter.as_ref().map(|f| f.to_string());
+        let marked = (!self.marked.is_empty()).then(|| {
+            format!(
+                "Marked: {} item(s), {}",
+                self.marked.len(),
+                crate::utils::format_size(self.marked_roots().map(|(_, size)| size).sum())
+            )
+        });
+        let help = match (&self.status, &self.name_filter) {
+            _ if self.view == View::Search && let Some(search) = &self.search => crate::ui::create_search_input(
+                search.mode.label(),
+                &search.query,
+                search.results.len(),
+                search.error.as_deref(),
+                marked,
+            ),
+            (_, Some(filter)) if filter.editing => {
+                crate::ui::create_filter_input(&filter.query, self.visible_entries.len(), marked)
+            }
+            (Some(message), _) => crate::ui::create_status_text(message, marked),
+            (None, _) => crate::ui::create_help_text(&self.footer_hints(filter.as_deref()), marked),
+        };
+
+        let overlay = &mut self.overlay;
+        let list_state = &mut self.list_state;
+        let keymap = &self.keymap;
+        let page_size = &mut self.page_size;
         self.terminal.draw(|f| {
-            let chunks = layout.split(f.size());
-            f.render_stateful_widget(list.clone(), chunks[0], &mut self.list_state);
-            f.render_widget(help.clone(), chunks[1]);
+            let panes: Vec<_> = info_panel.iter().chain(preview.iter()).collect();
+            let layout = crate::ui::create_main_layout(f.size(), panes.len());
+            *page_size = usize::from(layout.list.height.saturating_sub(2)).max(1);
+            f.render_stateful_widget(list.clone(), layout.list, list_state);
+            for (pane, area) in panes.into_iter().zip(layout.side) {
+                f.render_widget(pane.clone(), area);
+            }
+            f.render_widget(help.clone(), layout.footer);
+
+            match overlay {
+                Some(Overlay::ExtensionPicker(picker)) => {
+                    let area = crate::ui::centered_rect(60, 70, f.size());
+                    let popup = crate::ui::create_extension_picker(
+                        &app.extension_choices,
+                        app.extension_stats.iter().map(|s| s.size).sum(),
+                        &picker.checked,
+                        picker.ignore_case,
+                        keymap.short_label(Action::ToggleCase),
+                    );
+                    f.render_widget(Clear, area);
+                    f.render_stateful_widget(popup, area, &mut picker.state);
+                }
+                Some(Overlay::ConfirmRemoval { removal, targets }) => {
+                    let area = crate::ui::centered_rect(60, 40, f.size());
+                    let size = crate::utils::format_size(targets.iter().map(|(_, size)| size).sum());
+                    let (title, question) = match removal {
+                        Removal::Trash => ("Trash", format!("Move {} item(s), {}, to the trash?", targets.len(), size)),
+                        Removal::Delete => (
+                            "Delete",
+                            format!("Permanently delete {} item(s), {}? This cannot be undone.", targets.len(), size),
+                        ),
+                        Removal::Purge => (
+                            "Purge",
+                            format!("Permanently delete {} trashed item(s), {}? This cannot be undone.", targets.len(), size),
+                        ),
+                    };
+                    let mut lines = vec![question, String::new()];
+                    lines.extend(targets.iter().map(|(p, _)| p.display().to_string()));
+                    f.render_widget(Clear, area);
+                    f.render_widget(crate::ui::create_confirm_dialog(title, lines), area);
+                }
+                Some(Overlay::Deleting(job)) => {
+                    let area = crate::ui::centered_rect(60, 20, f.size());
+                    let label = format!(
+                        "{} of {} freed ({} files)",
+                        crate::utils::format_size(job.freed),
+                        crate::utils::format_size(job.size),
+                        job.files
+                    );
+                    let ratio = if job.size > 0 { job.freed as f64 / job.size as f64 } else { 0.0 };
+                    f.render_widget(Clear, area);
+                    f.render_widget(
+                        crate::ui::create_progress_gauge(&format!("Deleting {} item(s)", job.items), label, ratio),
+                        area,
+                    );
+                }
+                Some(Overlay::Transferring(job)) => {
+                    let area = crate::ui::centered_rect(60, 20, f.size());
+                    let verb = job.transfer.verb();
+                    let label = format!(
+                        "{} of {} ({} files)",
+                        crate::utils::format_size(job.bytes),
+                        crate::utils::format_size(job.size),
+                        job.files
+                    );
+                    let ratio = if job.size > 0 { job.bytes as f64 / job.size as f64 } else { 0.0 };
+                    f.render_widget(Clear, area);
+                    f.render_widget(
+                        crate::ui::create_progress_gauge(&format!("{} {} item(s)", verb, job.items), label, ratio),
+                        area,
+                    );
+                }
+                Some(Overlay::Prompt { action, input, error }) => {
+                    let area = crate::ui::centered_rows(60, 3, f.size());
+                    f.render_widget(Clear, area);
+                    f.render_widget(crate::ui::create_prompt(action.title(), input, error.as_deref()), area);
+                }
+                Some(Overlay::Help { scroll }) => {
+                    let area = crate::ui::centered_rect(80, 80, f.size());
+                    f.render_widget(Clear, area);
+                    f.render_widget(crate::ui::create_keymap_help(keymap, *scroll), area);
+                }
+                None => {}
+            }
         })
         .map_err(|e| SpacemanError::Ui(e.to_string()))?;
 
@@ -94,22 +552,46 @@ impl Terminal {
         loop {
             if event::poll(Duration::from_millis(16))
                 .map_err(|e| SpacemanError::Ui(e.to_string()))?
-            {
-                if let Event::Key(key) = event::read()
+                && let Event::Key(key) = event::read()
                     .map_err(|e| SpacemanError::Ui(e.to_string()))?
-                {
-                    self.needs_redraw = true;
-                    if !self.handle_key_event(key.code, app)? {
-                        break;
-                    }
+            {
+                self.needs_redraw = true;
+                if !self.handle_key_event(Key::from_event(key), app)? {
+                    break;
                 }
             }
+            self.poll_delete(app);
+            self.poll_transfer(app);
+            self.poll_preview();
             self.draw(app)?;
         }
 
         Ok(())
     }
 
+    /// Hands the terminal over to `command` until it exits, then restores the
+    /// interface and forces a full redraw.
+    fn suspend(&mut self, mut command: Command) -> Result<std::process::ExitStatus, SpacemanError> {
+        self.cleanup()?;
+        // Keep the child on the terminal when our own streams are redirected
+        if !io::stdin().is_terminal() {
+            command.stdin(Stdio::from(File::open("/dev/tty")?));
+        }
+        if !io::stdout().is_terminal() {
+            command.stdout(Stdio::from(File::options().write(true).open("/dev/tty")?));
+        }
+        let status = command.status();
+
+        enable_raw_mode().map_err(SpacemanError::Io)?;
+        execute!(self.terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)
+            .map_err(SpacemanError::Io)?;
+        self.terminal
+            .clear()
+            .map_err(|e| SpacemanError::Ui(e.to_string()))?;
+        self.needs_redraw = true;
+        Ok(status?)
+    }
+
     pub fn cleanup(&mut self) -> Result<(), SpacemanError> {
         disable_raw_mode().map_err(SpacemanError::Io)?;
         execute!(
@@ -125,21 +607,7 @@ impl Terminal {
     }
 
     // Private helper methods
-    fn update_available_extensions(&mut self, entries: &[FileEntry]) {
-        let mut extensions = std::collections::HashSet::new();
-        for entry in entries {
-            if let Some(ext) = entry.path.extension() {
-                if let Some(ext_str) = ext.to_str() {
-                    extensions.insert(ext_str.to_string());
-                }
-            }
-        }
-        let mut ext_vec: Vec<String> = extensions.into_iter().collect();
-        ext_vec.sort();
-        self.available_extensions = ext_vec;
-    }
-
-    fn sort_entries(&self, entries: &mut Vec<FileEntry>) {
+    fn sort_entries(&self, entries: &mut [FileEntry]) {
         match self.sort_order.as_str() {
             "size" => {
                 entries.sort_by(|a, b| {
@@ -172,72 +640,456 @@ impl Terminal {
         }
     }
 
-    fn filter_entries(&self, entries: &[FileEntry]) -> Vec<FileEntry> {
-        if let Some(ext) = &self.filter_ext {
-            entries
-                .iter()
-                .filter(|entry| {
-                    entry
-                        .path
-                        .extension()
-                        .and_then(|e| e.to_str())
-                        .map(|e| e == ext)
-                        .unwrap_or(false)
-                })
-                .cloned()
-                .collect()
-        } else {
-            entries.to_vec()
+    fn filter_entries(&self, entries: &[FileEntry], root: &Path) -> Vec<FileEntry> {
+        entries
+            .iter()
+            .filter(|entry| self.filter.as_ref().is_none_or(|f| entry.is_dir || f.matches(&entry.path)))
+            .filter(|entry| self.name_matches(entry).is_some())
+            .filter(|entry| Self::query_keeps(&self.query, entry, root))
+            .cloned()
+            .collect()
+    }
+
+    /// Directories stay listed so the tree can still be browsed, unless the
+    /// expression itself says which types to show.
+    fn query_keeps(query: &Option<Query>, entry: &FileEntry, root: &Path) -> bool {
+        match query {
+            Some(query) => query.matches(entry, root) || (entry.is_dir && !query.mentions_type()),
+            None => true,
+        }
+    }
+
+    /// Char positions of the name that match the type-to-filter query, or
+    /// `None` if the entry is filtered out.
+    fn name_matches(&self, entry: &FileEntry) -> Option<Vec<usize>> {
+        match &self.name_filter {
+            Some(filter) => fuzzy_match(&filter.query, &entry.name).map(|m| m.positions),
+            None => Some(Vec::new()),
         }
     }
 
-    fn create_list_items<'a>(&self, entries: &'a [FileEntry]) -> Vec<ListItem<'a>> {
+    /// Re-applies the name filter, keeping the highlighted entry selected
+    /// if it still matches and falling back to the first match.
+    fn refilter(&mut self, app: &App) {
+        self.pending_selection = self.selected_path(app);
+        self.reset_selection();
+    }
+
+    fn list_len(&self, app: &App) -> usize {
+        match self.view {
+            View::Extensions => app.extension_stats.len(),
+            View::Artifacts => app.artifact_summary.len(),
+            View::Projects => app.projects.len(),
+            View::Trash => app.trash.len(),
+            _ => self.visible_entries.len(),
+        }
+    }
+
+    fn create_list_items(&self, entries: &[FileEntry]) -> Vec<ListItem<'static>> {
         entries
             .iter()
-            .map(|entry| crate::ui::create_list_item(entry, self.args.no_permissions, self.args.no_modified))
+            .enumerate()
+            .map(|(i, entry)| {
+                let highlight = match &self.search {
+                    Some(search) if self.view == View::Search => {
+                        search.results.get(i).map(|(_, positions)| positions.clone()).unwrap_or_default()
+                    }
+                    _ => {
+                        // The name is the tail of the displayed path
+                        let offset = entry.path.to_string_lossy().chars().count().saturating_sub(entry.name.chars().count());
+                        let positions = self.name_matches(entry).unwrap_or_default();
+                        positions.into_iter().map(|p| p + offset).collect()
+                    }
+                };
+                crate::ui::create_list_item(
+                    entry,
+                    self.marked.contains_key(&entry.path),
+                    &highlight,
+                    self.args.no_permissions,
+                    self.args.no_modified,
+                )
+            })
             .collect()
     }
 
-    fn handle_key_event(&mut self, key: KeyCode, app: &mut App) -> Result<bool, SpacemanError> {
-        match key {
-            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
-            KeyCode::Up => {
-                if let Some(selected) = self.list_state.selected() {
-                    if selected > 0 {
-                        self.list_state.select(Some(selected - 1));
+    fn handle_key_event(&mut self, key: Key, app: &mut App) -> Result<bool, SpacemanError> {
+        self.status = None;
+        // Text fields take keys as typed, except that the cancel binding
+        // (such as Ctrl-g) still works as esc and other chords type nothing
+        let code = match key.code {
+            _ if !key.is_plain() && self.keymap.is(key, Action::Cancel) => KeyCode::Esc,
+            KeyCode::Char(_) if !key.is_plain() => KeyCode::Null,
+            code => code,
+        };
+        if let Some(overlay) = self.overlay.take() {
+            match overlay {
+                Overlay::ExtensionPicker(picker) => self.handle_picker_key(key, picker, app),
+                Overlay::ConfirmRemoval { removal, targets } => {
+                    if matches!(code, KeyCode::Char('y' | 'Y')) {
+                        self.remove(removal, targets, app);
                     }
-                } else {
-                    self.list_state.select(Some(0));
+                }
+                // Keys are ignored until the deletion or transfer finishes
+                Overlay::Deleting(job) => self.overlay = Some(Overlay::Deleting(job)),
+                Overlay::Transferring(job) => self.overlay = Some(Overlay::Transferring(job)),
+                Overlay::Prompt { action, mut input, error } => match code {
+                    KeyCode::Esc => {}
+                    KeyCode::Enter => self.submit_prompt(action, input.trim(), app),
+                    KeyCode::Backspace => {
+                        input.pop();
+                        self.prompt(action, input);
+                    }
+                    KeyCode::Char(c) => {
+                        input.push(c);
+                        self.prompt(action, input);
+                    }
+                    _ => self.overlay = Some(Overlay::Prompt { action, input, error }),
+                },
+                Overlay::Help { scroll } => {
+                    let scroll = match self.keymap.resolve(&[key]) {
+                        Resolution::Action(Action::MoveUp) => scroll.saturating_sub(1),
+                        Resolution::Action(Action::MoveDown) => scroll.saturating_add(1),
+                        Resolution::Action(Action::PageUp) => scroll.saturating_sub(self.page_size as u16),
+                        Resolution::Action(Action::PageDown) => scroll.saturating_add(self.page_size as u16),
+                        Resolution::Action(Action::First) => 0,
+                        // Any other key closes the help
+                        _ => return Ok(true),
+                    };
+                    let last = (Action::ALL.len() as u16).saturating_sub(1);
+                    self.overlay = Some(Overlay::Help { scroll: scroll.min(last) });
                 }
             }
-            KeyCode::Down => {
-                if let Some(selected) = self.list_state.selected() {
-                    if selected < app.entries.len() - 1 {
-                        self.list_state.select(Some(selected + 1));
+            return Ok(true);
+        }
+
+        if self.view == View::Search
+            && let Some(search) = &mut self.search
+        {
+            match code {
+                KeyCode::Esc => {
+                    self.view = View::Browse;
+                    self.reset_selection();
+                    return Ok(true);
+                }
+                KeyCode::Tab => {
+                    search.mode = search.mode.next();
+                    self.run_search(app);
+                    return Ok(true);
+                }
+                KeyCode::Backspace => {
+                    search.query.pop();
+                    self.run_search(app);
+                    return Ok(true);
+                }
+                KeyCode::Char(c) => {
+                    search.query.push(c);
+                    self.run_search(app);
+                    return Ok(true);
+                }
+                KeyCode::Enter | KeyCode::Right => {
+                    if let Some(path) = self.selected_path(app) {
+                        self.reveal(path, app);
                     }
-                } else {
-                    self.list_state.select(Some(0));
+                    return Ok(true);
+                }
+                // Movement keys go through the results
+                _ if self.moves(key) => {}
+                _ => return Ok(true),
+            }
+        }
+
+        if let Some(filter) = &mut self.name_filter
+            && filter.editing
+        {
+            match code {
+                KeyCode::Esc => {
+                    self.name_filter = None;
+                    self.refilter(app);
+                    return Ok(true);
+                }
+                KeyCode::Enter => {
+                    filter.editing = false;
+                    if filter.query.is_empty() {
+                        self.name_filter = None;
+                    }
+                    return Ok(true);
+                }
+                KeyCode::Backspace => {
+                    filter.query.pop();
+                    self.refilter(app);
+                    return Ok(true);
+                }
+                KeyCode::Char(c) => {
+                    filter.query.push(c);
+                    self.refilter(app);
+                    return Ok(true);
+                }
+                // Movement keys still go through the narrowed list
+                _ if self.moves(key) => {}
+                _ => return Ok(true),
+            }
+        }
+
+        let Some(action) = self.action_for(key) else {
+            return Ok(true);
+        };
+        match action {
+            Action::Cancel if self.name_filter.is_some() => {
+                self.name_filter = None;
+                self.refilter(app);
+            }
+            Action::FilterNames if self.lists_entries() => match &mut self.name_filter {
+                Some(filter) => filter.editing = true,
+                None => {
+                    self.name_filter = Some(NameFilter {
+                        query: String::new(),
+                        editing: true,
+                    })
                 }
+            },
+            Action::Cancel | Action::Back if self.view == View::ArtifactDirs => {
+                self.view = View::Artifacts;
+                self.reset_selection();
+            }
+            Action::Cancel | Action::Back if self.view != View::Browse => {
+                self.view = View::Browse;
+                self.reset_selection();
             }
-            KeyCode::Left => {
+            Action::Quit | Action::Cancel => return Ok(false),
+            Action::MoveUp => self.move_selection(-1, app),
+            Action::MoveDown => self.move_selection(1, app),
+            Action::PageUp => self.move_selection(-(self.page_size as isize), app),
+            Action::PageDown => self.move_selection(self.page_size as isize, app),
+            Action::First => self.list_state.select(Some(0)),
+            Action::Last => self.list_state.select(Some(self.list_len(app).saturating_sub(1))),
+            Action::Help => self.overlay = Some(Overlay::Help { scroll: 0 }),
+            Action::Back => {
+                self.name_filter = None;
                 if let Err(e) = app.navigate_back() {
-                    eprintln!("Error navigating back: {}", e);
+                    self.status = Some(format!("Error navigating back: {}", e));
                 }
                 self.reset_selection();
             }
-            KeyCode::Right | KeyCode::Enter => {
-                if let Some(selected) = self.list_state.selected() {
-                    if let Some(entry) = app.entries.get(selected) {
-                        if entry.is_dir {
-                            if let Err(e) = app.navigate_to(entry.path.clone()) {
-                                eprintln!("Error navigating to directory: {}", e);
-                            }
-                            self.reset_selection();
+            Action::Open if matches!(self.view, View::LargestFiles | View::ArtifactDirs) =>
+            {
+                if let Some(selected) = self.list_state.selected()
+                    && let Some(entry) = self.visible_entries.get(selected)
+                {
+                    let path = entry.path.clone();
+                    self.reveal(path, app);
+                }
+            }
+            Action::Open if self.view == View::Projects => {
+                if let Some(selected) = self.list_state.selected()
+                    && let Some(project) = app.projects.get(selected)
+                {
+                    if let Err(e) = app.navigate_to(project.path.clone()) {
+                        self.status = Some(format!("Error navigating to directory: {}", e));
+                    }
+                    self.view = View::Browse;
+                    self.reset_selection();
+                }
+            }
+            Action::Open if self.view == View::Artifacts => {
+                if let Some(selected) = self.list_state.selected()
+                    && let Some(summary) = app.artifact_summary.get(selected)
+                {
+                    self.artifact_tool = Some(summary.tool.clone());
+                    self.view = View::ArtifactDirs;
+                    self.reset_selection();
+                }
+            }
+            Action::Open if self.view == View::Extensions => {
+                if let Some(selected) = self.list_state.selected()
+                    && let Some(stats) = app.extension_stats.get(selected)
+                {
+                    let ignore_case = self.filter.as_ref().map_or(self.args.ignore_case, |f| f.ignore_case());
+                    self.apply_filter(ExtensionFilter::from_list(&[&stats.extension], ignore_case), app);
+                    self.view = View::Browse;
+                    self.reset_selection();
+                }
+            }
+            Action::Open => {
+                if let Some(selected) = self.list_state.selected()
+                    && let Some(entry) = self.visible_entries.get(selected)
+                    && !entry.skipped
+                    && !entry.ignored_bucket
+                {
+                    if entry.is_dir {
+                        if let Err(e) = app.navigate_to(entry.path.clone()) {
+                            self.status = Some(format!("Error navigating to directory: {}", e));
                         }
+                        self.name_filter = None;
+                        self.reset_selection();
+                    } else if self.args.pick {
+                        return Ok(!self.pick(app));
+                    } else {
+                        let path = entry.path.clone();
+                        self.edit(path.clone());
+                        self.rescan(app);
+                        self.pending_selection = Some(path);
+                    }
+                }
+            }
+            Action::Choose if self.args.pick && self.lists_entries() => return Ok(!self.pick(app)),
+            Action::Preview => {
+                self.preview = match self.preview.take() {
+                    Some(_) => None,
+                    None => Some(PreviewPane::new()),
+                };
+            }
+            Action::Info => {
+                self.info = match self.info.take() {
+                    Some(_) => None,
+                    None => Some(InfoPanel::new(self.selected_path(app))),
+                };
+            }
+            Action::Shell => {
+                let selected = self.list_state.selected().and_then(|i| self.visible_entries.get(i));
+                let dir = match selected {
+                    Some(entry) if self.lists_entries() && entry.is_dir && !entry.skipped && !entry.ignored_bucket => {
+                        entry.path.clone()
+                    }
+                    _ => app.current_path.clone(),
+                };
+                let reselect = selected.map(|entry| entry.path.clone());
+
+                let mut command = crate::utils::command_from_env(&["SHELL"], "/bin/sh");
+                command.current_dir(&dir);
+                if let Err(e) = self.suspend(command) {
+                    self.status = Some(format!("Error starting shell in {}: {}", dir.display(), e));
+                }
+                // Whatever was cleaned up by hand shows up in the sizes
+                self.rescan(app);
+                self.pending_selection = reselect;
+            }
+            Action::View => {
+                match self.selected_path(app) {
+                    Some(path) if path.is_file() => self.view_file(path),
+                    Some(_) => self.status = Some("Only files can be viewed".to_string()),
+                    None => {}
+                }
+            }
+            Action::OpenExternal if self.lists_entries() => {
+                if let Some(entry) = self.list_state.selected().and_then(|i| self.visible_entries.get(i))
+                    && let Err(e) = open::that_detached(&entry.path)
+                {
+                    self.status = Some(format!("Error opening {}: {}", entry.path.display(), e));
+                }
+            }
+            Action::LargestFiles => {
+                app.scan_largest_files(self.top_limit);
+                self.view = View::LargestFiles;
+                self.reset_selection();
+            }
+            Action::Extensions => {
+                app.scan_extension_breakdown();
+                self.view = View::Extensions;
+                self.reset_selection();
+            }
+            Action::Artifacts => {
+                app.scan_artifacts();
+                self.view = View::Artifacts;
+                self.reset_selection();
+            }
+            Action::Projects => {
+                app.scan_projects();
+                self.view = View::Projects;
+                self.reset_selection();
+            }
+            Action::Restore if self.view == View::Trash => {
+                if let Some(item) = self.list_state.selected().and_then(|i| app.trash.get(i)) {
+                    let restored = crate::core::restore(item);
+                    self.status = Some(match &restored {
+                        Ok(()) => format!("Restored {}", item.original_path.display()),
+                        Err(e) => format!("Error restoring: {}", e),
+                    });
+                    if restored.is_ok() {
+                        self.rescan(app);
                     }
+                    app.scan_trash();
                 }
             }
-            KeyCode::Char('s') => {
+            Action::Trash | Action::Delete if self.view == View::Trash => {
+                if let Some(item) = self.list_state.selected().and_then(|i| app.trash.get(i)) {
+                    self.overlay = Some(Overlay::ConfirmRemoval {
+                        removal: Removal::Purge,
+                        targets: vec![(item.path.clone(), item.size)],
+                    });
+                }
+            }
+            Action::Trash => self.confirm_removal(Removal::Trash, app),
+            Action::Delete => self.confirm_removal(Removal::Delete, app),
+            Action::Find => {
+                self.view = View::Search;
+                self.name_filter = None;
+                match &self.search {
+                    Some(_) => self.run_search(app),
+                    None => {
+                        self.search = Some(Search {
+                            query: String::new(),
+                            mode: SearchMode::Glob,
+                            results: Vec::new(),
+                            error: None,
+                        })
+                    }
+                }
+            }
+            Action::ShowTrash => {
+                app.scan_trash();
+                self.view = View::Trash;
+                self.reset_selection();
+            }
+            Action::Mark if self.lists_entries() => {
+                if let Some(selected) = self.list_state.selected()
+                    && let Some(entry) = self.visible_entries.get(selected)
+                    && Self::markable(entry, app)
+                {
+                    if self.marked.remove(&entry.path).is_none() {
+                        self.marked.insert(entry.path.clone(), entry.size);
+                    }
+                    if selected + 1 < self.visible_entries.len() {
+                        self.list_state.select(Some(selected + 1));
+                    }
+                }
+            }
+            Action::MarkAll if self.lists_entries() => {
+                for entry in self.visible_entries.iter().filter(|e| Self::markable(e, app)) {
+                    self.marked.insert(entry.path.clone(), entry.size);
+                }
+            }
+            Action::InvertMarks if self.lists_entries() => {
+                for entry in self.visible_entries.iter().filter(|e| Self::markable(e, app)) {
+                    if self.marked.remove(&entry.path).is_none() {
+                        self.marked.insert(entry.path.clone(), entry.size);
+                    }
+                }
+            }
+            Action::Where => {
+                let current = self.query.as_ref().map(|q| q.to_string()).unwrap_or_default();
+                self.prompt(PromptAction::Where, current);
+            }
+            Action::MarkPattern if self.lists_entries() => self.prompt(PromptAction::MarkPattern, String::new()),
+            Action::ClearMarks => self.marked.clear(),
+            Action::Move if self.lists_entries() => self.prompt(PromptAction::MoveTo, String::new()),
+            Action::Archive if self.lists_entries() => {
+                let default = app.current_path.join("archive.tar.gz");
+                self.prompt(PromptAction::ArchiveTo, default.display().to_string());
+            }
+            Action::CopyPaths if self.lists_entries() => {
+                let paths: Vec<String> = self
+                    .targets(app)
+                    .iter()
+                    .map(|(path, _)| path.display().to_string())
+                    .collect();
+                if !paths.is_empty() {
+                    self.status = Some(match self.copy_to_clipboard(&paths.join("\n")) {
+                        Ok(()) => format!("Copied {} path(s) to the clipboard", paths.len()),
+                        Err(e) => format!("Error copying paths: {}", e),
+                    });
+                }
+            }
+            Action::Sort => {
                 self.sort_order = match self.sort_order.as_str() {
                     "default" => "size".to_string(),
                     "size" => "name".to_string(),
@@ -246,25 +1098,620 @@ impl Terminal {
                     _ => "default".to_string(),
                 };
             }
-            KeyCode::Char('r') => {
-                self.filter_ext = None;
-                self.current_ext_index = 0;
+            Action::ResetFilter => {
+                self.apply_filter(None, app);
             }
-            KeyCode::Char('f') => {
-                if self.available_extensions.is_empty() {
-                    self.filter_ext = None;
-                } else {
-                    self.current_ext_index = (self.current_ext_index + 1) % (self.available_extensions.len() + 1);
-                    self.filter_ext = if self.current_ext_index == 0 {
-                        None
+            Action::FilterExtensions => {
+                app.scan_extension_breakdown();
+                let (checked, ignore_case) = match &self.filter {
+                    Some(filter) => (filter.extensions().iter().cloned().collect(), filter.ignore_case()),
+                    None => (BTreeSet::new(), self.args.ignore_case),
+                };
+                let mut state = ListState::default();
+                state.select(Some(0));
+                self.overlay = Some(Overlay::ExtensionPicker(ExtensionPicker {
+                    checked,
+                    ignore_case,
+                    state,
+                }));
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn handle_picker_key(&mut self, key: Key, mut picker: ExtensionPicker, app: &mut App) {
+        let selected = picker.state.selected().unwrap_or(0);
+        let action = match self.keymap.resolve(&[key]) {
+            Resolution::Action(action) => Some(action),
+            _ => None,
+        };
+        match action {
+            _ if self.keymap.is(key, Action::ToggleCase) => {
+                picker.ignore_case = !picker.ignore_case;
+                if picker.ignore_case {
+                    picker.checked = picker.checked.iter().map(|e| e.to_lowercase()).collect();
+                }
+            }
+            Some(Action::Cancel | Action::Quit) => return,
+            Some(Action::Open) => {
+                let checked: Vec<&String> = picker.checked.iter().collect();
+                self.apply_filter(ExtensionFilter::from_list(&checked, picker.ignore_case), app);
+                return;
+            }
+            Some(Action::MoveUp) => picker.state.select(Some(selected.saturating_sub(1))),
+            Some(Action::MoveDown) if selected + 1 < app.extension_choices.len() => {
+                picker.state.select(Some(selected + 1));
+            }
+            Some(Action::Mark) => {
+                if let Some(stats) = app.extension_choices.get(selected) {
+                    let key = if picker.ignore_case {
+                        stats.extension.to_lowercase()
                     } else {
-                        Some(self.available_extensions[self.current_ext_index - 1].clone())
+                        stats.extension.clone()
                     };
+                    if !picker.checked.remove(&key) {
+                        picker.checked.insert(key);
+                    }
                 }
             }
             _ => {}
         }
-        Ok(true)
+        self.overlay = Some(Overlay::ExtensionPicker(picker));
+    }
+
+    /// Chooses the marked entries, or the selected one, and reports whether
+    /// anything was picked so the caller can quit.
+    fn pick(&mut self, app: &App) -> bool {
+        let targets = self.targets(app);
+        if targets.is_empty() {
+            return false;
+        }
+        self.picked = Some(targets.into_iter().map(|(path, _)| path).collect());
+        true
+    }
+
+    pub fn take_picked(&mut self) -> Option<Vec<PathBuf>> {
+        self.picked.take()
+    }
+
+    /// Opens `path` in `$VISUAL` or `$EDITOR`, falling back to `$PAGER` and
+    /// then `less`.
+    fn edit(&mut self, path: PathBuf) {
+        self.run_on(crate::utils::command_from_env(&["VISUAL", "EDITOR", "PAGER"], "less"), path);
+    }
+
+    /// Opens `path` in `$PAGER`, falling back to `less`.
+    fn view_file(&mut self, path: PathBuf) {
+        self.run_on(crate::utils::command_from_env(&["PAGER"], "less"), path);
+    }
+
+    /// Runs `command` on `path` in the foreground, reporting failures in the
+    /// status line.
+    fn run_on(&mut self, mut command: Command, path: PathBuf) {
+        command.arg(&path);
+        let program = command.get_program().to_string_lossy().into_owned();
+        match self.suspend(command) {
+            Ok(status) if !status.success() => {
+                self.status = Some(format!("{} exited with {}", program, status));
+            }
+            Ok(_) => {}
+            Err(e) => self.status = Some(format!("Error running {}: {}", program, e)),
+        }
+    }
+
+    /// Adds `key` to the keys pressed so far and returns the action they
+    /// complete. A key that continues no binding starts over on its own.
+    fn action_for(&mut self, key: Key) -> Option<Action> {
+        self.pending_keys.push(key);
+        loop {
+            match self.keymap.resolve(&self.pending_keys) {
+                Resolution::Action(action) => {
+                    self.pending_keys.clear();
+                    return Some(action);
+                }
+                Resolution::Prefix => return None,
+                Resolution::Unbound if self.pending_keys.len() > 1 => self.pending_keys = vec![key],
+                Resolution::Unbound => {
+                    self.pending_keys.clear();
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Whether `key` alone moves the selection, so passes through text fields.
+    fn moves(&self, key: Key) -> bool {
+        matches!(
+            self.keymap.resolve(&[key]),
+            Resolution::Action(
+                Action::MoveUp | Action::MoveDown | Action::PageUp | Action::PageDown | Action::First | Action::Last
+            )
+        )
+    }
+
+    fn move_selection(&mut self, by: isize, app: &App) {
+        let last = self.list_len(app).saturating_sub(1);
+        let selected = match self.list_state.selected() {
+            Some(selected) => selected.saturating_add_signed(by).min(last),
+            None => 0,
+        };
+        self.list_state.select(Some(selected));
+    }
+
+    /// The footer's key hints, taken from the active keymap.
+    fn footer_hints(&self, filter: Option<&str>) -> Vec<(String, String)> {
+        let keys = |action| self.keymap.short_label(action);
+        let mut hints = Vec::new();
+        if let Some(help) = keys(Action::Help) {
+            hints.push((help, "Help".to_string()));
+        }
+        if let (Some(up), Some(down)) = (keys(Action::MoveUp), keys(Action::MoveDown)) {
+            hints.push((format!("{}/{}", up, down), "Navigate".to_string()));
+        }
+        if let (Some(back), Some(open)) = (keys(Action::Back), keys(Action::Open)) {
+            hints.push((format!("{}/{}", back, open), "Back / Forward".to_string()));
+        }
+        let labelled = [
+            (Action::Quit, "Quit".to_string()),
+            (Action::Sort, format!("Sort ({})", self.sort_order)),
+            (Action::FilterExtensions, format!("Filter ({})", filter.unwrap_or("none"))),
+            (Action::ResetFilter, "Reset filter".to_string()),
+            (Action::LargestFiles, "Largest files".to_string()),
+            (Action::Extensions, "Extensions".to_string()),
+            (Action::Artifacts, "Artifacts".to_string()),
+            (Action::Projects, "Projects".to_string()),
+            (Action::Trash, "Trash".to_string()),
+            (Action::Delete, "Delete".to_string()),
+            (Action::ShowTrash, "View trash".to_string()),
+            (Action::Mark, "Mark".to_string()),
+            (Action::MarkAll, "Mark all".to_string()),
+            (Action::InvertMarks, "Invert marks".to_string()),
+            (Action::MarkPattern, "Mark by pattern".to_string()),
+            (Action::ClearMarks, "Clear marks".to_string()),
+            (Action::Move, "Move".to_string()),
+            (Action::Archive, "Archive".to_string()),
+            (Action::CopyPaths, "Copy paths".to_string()),
+            (Action::OpenExternal, "Open".to_string()),
+            (Action::Shell, "Shell".to_string()),
+            (Action::Preview, "Preview".to_string()),
+            (Action::Info, "Info".to_string()),
+            (Action::FilterNames, "Filter names".to_string()),
+            (Action::Find, "Find".to_string()),
+            (Action::Where, "Where".to_string()),
+        ];
+        hints.extend(labelled.into_iter().filter_map(|(action, label)| Some((keys(action)?, label))));
+        hints
+    }
+
+    /// Whether the current view lists files and directories that can be
+    /// marked and acted on.
+    fn lists_entries(&self) -> bool {
+        matches!(self.view, View::Browse | View::LargestFiles | View::ArtifactDirs)
+    }
+
+    /// Scanned entries below the directory being browsed can be marked.
+    fn markable(entry: &FileEntry, app: &App) -> bool {
+        !entry.ignored_bucket && !entry.skipped && !app.current_path.starts_with(&entry.path)
+    }
+
+    /// The marked entries that are not inside another marked directory,
+    /// whose size already includes them.
+    fn marked_roots(&self) -> impl Iterator<Item = (&PathBuf, &u64)> {
+        // Paths order by component, so a directory's descendants follow it
+        let mut root: Option<&PathBuf> = None;
+        self.marked.iter().filter(move |(path, _)| {
+            if root.is_some_and(|root| path.starts_with(root)) {
+                return false;
+            }
+            root = Some(path);
+            true
+        })
+    }
+
+    /// The marked entries, or the selected one when nothing is marked.
+    fn targets(&self, app: &App) -> Vec<(PathBuf, u64)> {
+        if !self.marked.is_empty() {
+            return self.marked_roots().map(|(path, size)| (path.clone(), *size)).collect();
+        }
+        self.list_state
+            .selected()
+            .and_then(|i| self.visible_entries.get(i))
+            .filter(|entry| Self::markable(entry, app))
+            .map(|entry| vec![(entry.path.clone(), entry.size)])
+            .unwrap_or_default()
+    }
+
+    fn confirm_removal(&mut self, removal: Removal, app: &App) {
+        if !self.lists_entries() {
+            return;
+        }
+        if self.marked.keys().any(|path| app.current_path.starts_with(path)) {
+            self.status = Some("Cannot delete the directory being browsed".to_string());
+            return;
+        }
+
+        let targets = self.targets(app);
+        if !targets.is_empty() {
+            self.overlay = Some(Overlay::ConfirmRemoval { removal, targets });
+        } else if self.list_state.selected().is_some_and(|i| i < self.visible_entries.len()) {
+            self.status = Some("Only scanned files and directories below the current one can be deleted".to_string());
+        }
+    }
+
+    fn prompt(&mut self, action: PromptAction, input: String) {
+        self.overlay = Some(Overlay::Prompt { action, input, error: None });
+    }
+
+    fn submit_prompt(&mut self, action: PromptAction, input: &str, app: &mut App) {
+        if action == PromptAction::Where {
+            self.query = match input {
+                "" => None,
+                _ => match input.parse() {
+                    Ok(query) => Some(query),
+                    Err(error) => {
+                        // Keep the input so the mistake can be fixed in place
+                        self.overlay = Some(Overlay::Prompt { action, input: input.to_string(), error: Some(error) });
+                        return;
+                    }
+                },
+            };
+            self.refilter(app);
+            if self.view == View::Search {
+                self.run_search(app);
+            }
+            return;
+        }
+        if input.is_empty() {
+            return;
+        }
+        match action {
+            PromptAction::MarkPattern => match globset::Glob::new(input) {
+                Ok(glob) => {
+                    let matcher = glob.compile_matcher();
+                    let before = self.marked.len();
+                    for entry in self.visible_entries.iter().filter(|e| Self::markable(e, app)) {
+                        if entry.path.file_name().is_some_and(|name| matcher.is_match(name)) {
+                            self.marked.insert(entry.path.clone(), entry.size);
+                        }
+                    }
+                    self.status = Some(format!("Marked {} more item(s)", self.marked.len() - before));
+                }
+                Err(e) => self.status = Some(format!("Invalid pattern: {}", e)),
+            },
+            PromptAction::MoveTo | PromptAction::ArchiveTo => {
+                let destination = app.current_path.join(input);
+                let targets = self.targets(app);
+                if targets.is_empty() {
+                    return;
+                }
+                let items = targets.len();
+                let size = targets.iter().map(|(_, size)| size).sum();
+                let (events, transfer) = if action == PromptAction::MoveTo {
+                    (spawn_move(targets, destination.clone()), Transfer::Move(destination))
+                } else {
+                    (spawn_archive(targets, destination.clone()), Transfer::Archive(destination))
+                };
+                self.overlay = Some(Overlay::Transferring(TransferJob {
+                    events,
+                    transfer,
+                    items,
+                    size,
+                    bytes: 0,
+                    files: 0,
+                }));
+            }
+            PromptAction::Where => {}
+        }
+    }
+
+    /// Applies progress from a running move, archive or trashing and, once
+    /// it finishes, updates the listings.
+    fn poll_transfer(&mut self, app: &mut App) {
+        let Some(Overlay::Transferring(job)) = &mut self.overlay else {
+            return;
+        };
+
+        while let Ok(event) = job.events.try_recv() {
+            self.needs_redraw = true;
+            match event {
+                TransferEvent::Progress { bytes, files } => {
+                    job.bytes = bytes;
+                    job.files = files;
+                }
+                TransferEvent::Finished { targets, errors } => {
+                    let Some(Overlay::Transferring(job)) = self.overlay.take() else {
+                        return;
+                    };
+                    for target in &targets {
+                        self.marked.remove(&target.path);
+                    }
+                    match &job.transfer {
+                        Transfer::Move(destination) => {
+                            app.forget_deleted(&targets);
+                            self.forget_info();
+                            self.status = Some(Self::summary("Moved", targets.len(), &errors));
+                            if !targets.is_empty() && destination.starts_with(&app.current_path) {
+                                self.rescan(app);
+                            }
+                        }
+                        Transfer::Archive(destination) => {
+                            self.status = Some(match errors.first() {
+                                None => format!("Archived {} item(s) to {}", targets.len(), destination.display()),
+                                Some(e) => format!("Error archiving: {}", e),
+                            });
+                            if !targets.is_empty() && destination.starts_with(&app.current_path) {
+                                self.rescan(app);
+                            }
+                        }
+                        Transfer::Trash => {
+                            app.forget_deleted(&targets);
+                            self.forget_info();
+                            let size = crate::utils::format_size(targets.iter().map(|t| t.freed).sum());
+                            self.status = Some(match errors.first() {
+                                None => format!("Moved {} item(s), {}, to the trash", targets.len(), size),
+                                Some(first) => format!(
+                                    "Moved {} item(s), {}, to the trash, {} error(s): {}",
+                                    targets.len(),
+                                    size,
+                                    errors.len(),
+                                    first
+                                ),
+                            });
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Drops the details the info panel loaded, which may have gone stale.
+    fn forget_info(&mut self) {
+        if let Some(panel) = &mut self.info {
+            panel.loaded.clear();
+            let path = panel.path.take();
+            panel.show(path);
+        }
+    }
+
+    /// Rescans the current directory, reporting a failure in the status line.
+    fn rescan(&mut self, app: &mut App) {
+        self.forget_info();
+        if let Err(e) = app.refresh() {
+            self.status = Some(format!("Error rescanning: {}", e));
+        }
+    }
+
+    fn summary(verb: &str, done: usize, errors: &[String]) -> String {
+        match errors.first() {
+            None => format!("{} {} item(s)", verb, done),
+            Some(first) => format!("{} {} item(s), {} error(s): {}", verb, done, errors.len(), first),
+        }
+    }
+
+    /// Sets the system clipboard through the terminal with an OSC 52
+    /// sequence, which also works over SSH and inside tmux.
+    fn copy_to_clipboard(&mut self, text: &str) -> io::Result<()> {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+        let backend = self.terminal.backend_mut();
+        write!(backend, "\x1b]52;c;{}\x07", encoded)?;
+        Write::flush(backend)
+    }
+
+    fn remove(&mut self, removal: Removal, targets: Vec<(PathBuf, u64)>, app: &mut App) {
+        match removal {
+            Removal::Trash => {
+                self.overlay = Some(Overlay::Transferring(TransferJob {
+                    items: targets.len(),
+                    size: targets.iter().map(|(_, size)| size).sum(),
+                    events: spawn_trash(targets),
+                    transfer: Transfer::Trash,
+                    bytes: 0,
+                    files: 0,
+                }));
+            }
+            Removal::Delete | Removal::Purge => {
+                if removal == Removal::Purge {
+                    // Dropping the .trashinfo first leaves at worst an orphaned
+                    // file, which the trash specification allows cleaning up
+                    for (path, _) in &targets {
+                        if let Some(item) = app.trash.iter().find(|item| item.path == *path) {
+                            let _ = std::fs::remove_file(&item.info_path);
+                        }
+                    }
+                }
+                let (paths, sizes): (Vec<PathBuf>, Vec<u64>) = targets.into_iter().unzip();
+                self.overlay = Some(Overlay::Deleting(DeleteJob {
+                    items: paths.len(),
+                    events: spawn_delete(paths),
+                    size: sizes.iter().sum(),
+                    freed: 0,
+                    files: 0,
+                }));
+            }
+        }
+    }
+
+    /// The path of the highlighted row, in any view that lists paths.
+    fn selected_path(&self, app: &App) -> Option<PathBuf> {
+        let selected = self.list_state.selected()?;
+        match self.view {
+            View::Projects => app.projects.get(selected).map(|p| p.path.clone()),
+            View::Trash => app.trash.get(selected).map(|item| item.path.clone()),
+            View::Search => self.visible_entries.get(selected).map(|entry| entry.path.clone()),
+            _ if self.lists_entries() => self
+                .visible_entries
+                .get(selected)
+                .filter(|entry| !entry.skipped && !entry.ignored_bucket)
+                .map(|entry| entry.path.clone()),
+            _ => None,
+        }
+    }
+
+    fn poll_preview(&mut self) {
+        let Some(pane) = &mut self.preview else {
+            return;
+        };
+        while let Ok((path, preview)) = pane.results.try_recv() {
+            if pane.path.as_ref() == Some(&path) {
+                pane.content = Some(preview);
+                self.needs_redraw = true;
+            }
+        }
+    }
+
+    /// Applies progress from a running deletion and, once it finishes,
+    /// updates the listings in place.
+    fn poll_delete(&mut self, app: &mut App) {
+        let Some(Overlay::Deleting(job)) = &mut self.overlay else {
+            return;
+        };
+
+        while let Ok(event) = job.events.try_recv() {
+            self.needs_redraw = true;
+            match event {
+                DeleteEvent::Progress { freed, files } => {
+                    job.freed = freed;
+                    job.files = files;
+                }
+                DeleteEvent::Finished { targets, errors } => {
+                    let freed: u64 = targets.iter().map(|t| t.freed).sum();
+                    let removed = targets.iter().filter(|t| t.removed).count();
+                    app.forget_deleted(&targets);
+                    self.forget_info();
+                    for target in targets.iter().filter(|t| t.removed) {
+                        self.marked.remove(&target.path);
+                    }
+                    if self.view == View::Trash {
+                        app.scan_trash();
+                    }
+                    self.status = Some(match errors.first() {
+                        None => format!("Deleted {} item(s), freed {}", removed, crate::utils::format_size(freed)),
+                        Some(first) => format!(
+                            "Deleted {} item(s), freed {}, {} error(s): {}",
+                            removed,
+                            crate::utils::format_size(freed),
+                            errors.len(),
+                            first
+                        ),
+                    });
+                    self.overlay = None;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Matches the query against every indexed path below the current
+    /// directory. Globs without a `/` match file names, like `--exclude`;
+    /// regexes and fuzzy queries match the path.
+    fn run_search(&mut self, app: &App) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        search.results.clear();
+        search.error = None;
+        self.list_state.select(Some(0));
+        if search.query.is_empty() {
+            return;
+        }
+
+        let root = &app.current_path;
+        let candidates = app
+            .tree
+            .iter()
+            .filter(|e| e.path != *root && e.path.starts_with(root) && Self::query_keeps(&self.query, e, root));
+        let chars = |text: &str| text.chars().count();
+        match search.mode {
+            SearchMode::Glob => {
+                let matcher = match globset::Glob::new(search.query.trim_end_matches('/')) {
+                    Ok(glob) => glob.compile_matcher(),
+                    Err(e) => {
+                        search.error = Some(e.kind().to_string());
+                        return;
+                    }
+                };
+                let by_name = !search.query.trim_end_matches('/').contains('/');
+                for entry in candidates {
+                    let path = entry.path.to_string_lossy();
+                    let relative = entry.path.strip_prefix(root).unwrap_or(&entry.path);
+                    if by_name && matcher.is_match(&entry.name) {
+                        let start = chars(&path) - chars(&entry.name);
+                        search.results.push((entry.clone(), (start..chars(&path)).collect()));
+                    } else if !by_name && (matcher.is_match(relative) || matcher.is_match(&entry.path)) {
+                        search.results.push((entry.clone(), Vec::new()));
+                    }
+                }
+            }
+            SearchMode::Regex => {
+                let regex = match regex::Regex::new(&search.query) {
+                    Ok(regex) => regex,
+                    Err(e) => {
+                        search.error = Some(e.to_string().lines().last().unwrap_or_default().to_string());
+                        return;
+                    }
+                };
+                for entry in candidates {
+                    let path = entry.path.to_string_lossy();
+                    if let Some(found) = regex.find(&path) {
+                        let start = chars(&path[..found.start()]);
+                        search.results.push((entry.clone(), (start..start + chars(found.as_str())).collect()));
+                    }
+                }
+            }
+            SearchMode::Fuzzy => {
+                let mut scored = Vec::new();
+                for entry in candidates {
+                    let path = entry.path.to_string_lossy();
+                    let relative = entry.path.strip_prefix(root).unwrap_or(&entry.path).to_string_lossy();
+                    if let Some(found) = fuzzy_match(&search.query, &relative) {
+                        let offset = chars(&path) - chars(&relative);
+                        let positions = found.positions.iter().map(|p| p + offset).collect();
+                        scored.push((found.score, entry.clone(), positions));
+                    }
+                }
+                // Best match first, larger first among equals
+                scored.sort_by_key(|(score, entry, _)| (std::cmp::Reverse(*score), std::cmp::Reverse(entry.size)));
+                search.results = scored.into_iter().map(|(_, entry, positions)| (entry, positions)).collect();
+            }
+        }
+        if search.mode != SearchMode::Fuzzy {
+            search.results.sort_by_key(|(entry, _)| std::cmp::Reverse(entry.size));
+        }
+        search.results.truncate(SEARCH_RESULTS);
+    }
+
+    /// Opens the parent directory of `path` in the browser with `path` selected.
+    fn reveal(&mut self, path: PathBuf, app: &mut App) {
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        if let Err(e) = app.navigate_to(parent.to_path_buf()) {
+            self.status = Some(format!("Error navigating to directory: {}", e));
+        }
+        self.view = View::Browse;
+        self.name_filter = None;
+        self.reset_selection();
+        self.pending_selection = Some(path);
+    }
+
+    fn apply_filter(&mut self, filter: Option<ExtensionFilter>, app: &mut App) {
+        self.filter = filter;
+        if let Err(e) = app.set_extension_filter(self.filter.clone()) {
+            self.status = Some(format!("Error filtering by extension: {}", e));
+        }
+        self.reset_selection();
+    }
+}
+
+/// The interface is drawn on stdout, or on the controlling terminal when
+/// stdout is redirected so that it stays free for results.
+fn ui_output() -> Result<Box<dyn Write>, SpacemanError> {
+    if io::stdout().is_terminal() {
+        Ok(Box::new(io::stdout()))
+    } else {
+        Ok(Box::new(File::options().write(true).open("/dev/tty")?))
     }
 }
 
@@ -0,0 +1,86 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use ratatui::style::Color;
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Colours the interface draws with, by role rather than by widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Directories and labels
+    pub primary: Color,
+    /// Matches, status messages and artifact tools
+    pub accent: Color,
+    pub marked: Color,
+    /// Excluded entries, hints and hex dumps
+    pub muted: Color,
+    pub error: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            primary: Color::LightBlue,
+            accent: Color::Yellow,
+            marked: Color::Magenta,
+            muted: Color::DarkGray,
+            error: Color::Red,
+        }
+    }
+}
+
+impl Theme {
+    pub const NAMES: &'static str = "default, light, mono";
+
+    /// A built-in theme: `default` for dark terminals, `light` for light
+    /// ones, and `mono` which leaves colours to the terminal.
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "light" => Some(Self {
+                primary: Color::Blue,
+                accent: Color::Magenta,
+                marked: Color::Red,
+                muted: Color::Gray,
+                error: Color::Red,
+            }),
+            "mono" => Some(Self {
+                primary: Color::Reset,
+                accent: Color::Reset,
+                marked: Color::Reset,
+                muted: Color::Reset,
+                error: Color::Reset,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Overrides one role with a colour name such as `cyan` or `#ff8800`.
+    pub fn set(&mut self, role: &str, color: &str) -> Result<(), String> {
+        let color = Color::from_str(color).map_err(|_| format!("Unknown colour '{}' for {}", color, role))?;
+        match role {
+            "primary" => self.primary = color,
+            "accent" => self.accent = color,
+            "marked" => self.marked = color,
+            "muted" => self.muted = color,
+            "error" => self.error = color,
+            _ => {
+                return Err(format!(
+                    "Unknown colour role '{}', expected one of primary, accent, marked, muted, error",
+                    role
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets the theme for the rest of the run. Only the first call has effect.
+pub fn set_theme(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+pub fn theme() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
@@ -1,30 +1,179 @@
+use std::collections::BTreeSet;
+
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Gauge, List, ListItem, Paragraph, Wrap,
+    },
 };
 
-use crate::core::FileEntry;
+use crate::ui::{theme, Action, Keymap};
+use crate::core::{ArtifactSummary, EntryInfo, ExtensionStats, FileEntry, Preview, Project, TrashItem};
 
-pub fn create_list<'a>(app: &'a crate::app::App, items: Vec<ListItem<'a>>) -> List<'a> {
+pub fn create_list<'a>(title: String, items: Vec<ListItem<'a>>) -> List<'a> {
     List::new(items)
         .block(
             Block::default()
-                .title(format!("⯈ {} ⯇", app.current_path.display()))
+                .title(title)
                 .borders(Borders::ALL),
         )
         .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
 }
 
-pub fn create_help_text<'a>(sort_order: &'a str, filter_ext: Option<&'a str>) -> Paragraph<'a> {
-    Paragraph::new(format!(
-        "↑/↓: Navigate | ←/→: Back / Forward | q / esc: Quit | s: Sort ({}) | f: Filter ({}) | r: Reset filter",
-        sort_order,
-        filter_ext.unwrap_or("none")
-    ))
-    .block(Block::default().borders(Borders::ALL))
+/// The footer, listing `(keys, what they do)` pairs.
+pub fn create_help_text(hints: &[(String, String)], marked: Option<String>) -> Paragraph<'static> {
+    let hints: Vec<String> = hints.iter().map(|(keys, label)| format!("{}: {}", keys, label)).collect();
+    Paragraph::new(hints.join(" | ")).block(footer_block(marked))
+}
+
+/// Every action with its keys and configuration name, scrolled down by
+/// `scroll` lines.
+pub fn create_keymap_help(keymap: &Keymap, scroll: u16) -> Paragraph<'static> {
+    let rows: Vec<(Action, String)> = Action::ALL
+        .iter()
+        .map(|&action| (action, keymap.label(action).unwrap_or_else(|| "unbound".to_string())))
+        .collect();
+    let keys_width = rows.iter().map(|(_, keys)| keys.chars().count()).max().unwrap_or(0) + 2;
+    let description_width = Action::ALL.iter().map(|a| a.description().len()).max().unwrap_or(0) + 2;
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|(action, keys)| {
+            Line::from(vec![
+                Span::styled(format!("{:<keys_width$}", keys), Style::default().fg(theme().accent)),
+                Span::raw(format!("{:<description_width$}", action.description())),
+                Span::styled(action.name(), Style::default().fg(theme().muted)),
+            ])
+        })
+        .collect();
+    Paragraph::new(lines).scroll((scroll, 0)).block(
+        Block::default()
+            .title(format!("⯈ Keys ({}) ⯇", keymap.name()))
+            .title(Title::from(" ↑/↓: Scroll | Any other key: Close ").position(Position::Bottom))
+            .borders(Borders::ALL),
+    )
+}
+
+pub fn create_filter_input(query: &str, matches: usize, marked: Option<String>) -> Paragraph<'static> {
+    Paragraph::new(Line::from(vec![
+        Span::styled("/", Style::default().fg(theme().accent)),
+        Span::raw(format!("{}█", query)),
+        Span::styled(
+            format!("  {} match(es) | Enter: Keep | esc: Clear", matches),
+            Style::default().fg(theme().muted),
+        ),
+    ]))
+    .block(footer_block(marked))
+}
+
+pub fn create_search_input(
+    mode: &str,
+    query: &str,
+    matches: usize,
+    error: Option<&str>,
+    marked: Option<String>,
+) -> Paragraph<'static> {
+    let hint = match error {
+        Some(error) => Span::styled(format!("  {}", error), Style::default().fg(theme().error)),
+        None => Span::styled(
+            format!("  {} match(es) | Tab: Mode | Enter: Go to | esc: Close", matches),
+            Style::default().fg(theme().muted),
+        ),
+    };
+    Paragraph::new(Line::from(vec![
+        Span::styled(format!("find ({}): ", mode), Style::default().fg(theme().accent)),
+        Span::raw(format!("{}█", query)),
+        hint,
+    ]))
+    .block(footer_block(marked))
+}
+
+pub fn create_status_text(message: &str, marked: Option<String>) -> Paragraph<'static> {
+    Paragraph::new(message.to_string())
+        .style(Style::default().fg(theme().accent))
+        .block(footer_block(marked))
+}
+
+fn footer_block(marked: Option<String>) -> Block<'static> {
+    let block = Block::default().borders(Borders::ALL);
+    match marked {
+        Some(marked) => block.title(Span::styled(
+            format!(" {} ", marked),
+            Style::default().fg(theme().marked).add_modifier(Modifier::BOLD),
+        )),
+        None => block,
+    }
+}
+
+/// A one-line input box. `error` replaces the key hints at the bottom when
+/// the last submission was rejected.
+pub fn create_prompt(title: &str, input: &str, error: Option<&str>) -> Paragraph<'static> {
+    let hint = match error {
+        Some(error) => Title::from(Span::styled(format!(" {} ", error), Style::default().fg(theme().error))),
+        None => Title::from(" Enter: Confirm | esc: Cancel "),
+    };
+    Paragraph::new(format!("{}█", input)).block(
+        Block::default()
+            .title(format!("⯈ {} ⯇", title))
+            .title(hint.position(Position::Bottom))
+            .borders(Borders::ALL),
+    )
+}
+
+pub fn create_confirm_dialog(title: &str, lines: Vec<String>) -> Paragraph<'static> {
+    let lines: Vec<Line> = lines.into_iter().map(Line::from).collect();
+    Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .title(format!("⯈ {} ⯇", title))
+                .title(Title::from(" y: Confirm | n / esc: Cancel ").position(Position::Bottom))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme().error)),
+        )
+}
+
+pub fn create_progress_gauge(title: &str, label: String, ratio: f64) -> Gauge<'static> {
+    Gauge::default()
+        .block(Block::default().title(format!("⯈ {} ⯇", title)).borders(Borders::ALL))
+        .gauge_style(Style::default().fg(theme().primary))
+        .label(label)
+        .ratio(ratio.clamp(0.0, 1.0))
+}
+
+/// `highlight` holds the char indices of the entry's path to emphasise, such
+/// as the characters a filter or search matched.
+pub fn create_list_item(
+    entry: &FileEntry,
+    marked: bool,
+    highlight: &[usize],
+    no_permissions: bool,
+    no_modified: bool,
+) -> ListItem<'static> {
+    let item = create_entry_item(entry, highlight, no_permissions, no_modified);
+    if marked {
+        item.style(Style::default().fg(theme().marked).add_modifier(Modifier::BOLD))
+    } else {
+        item
+    }
 }
 
-pub fn create_list_item<'a>(entry: &'a FileEntry, no_permissions: bool, no_modified: bool) -> ListItem<'a> {
+fn create_entry_item(entry: &FileEntry, highlight: &[usize], no_permissions: bool, no_modified: bool) -> ListItem<'static> {
+    if entry.ignored_bucket {
+        return ListItem::new(format!(
+            "[ ~ ] {} ({})",
+            entry.path.display(),
+            crate::utils::format_size(entry.size)
+        ))
+        .style(Style::default().fg(theme().muted).add_modifier(Modifier::ITALIC));
+    }
+
+    if entry.skipped {
+        let prefix = if entry.is_dir { "[ / ] " } else { "[ # ] " };
+        return ListItem::new(format!("{}{} (excluded)", prefix, entry.path.display()))
+            .style(Style::default().fg(theme().muted));
+    }
+
     let size = crate::utils::format_size(entry.size);
     let prefix = if entry.is_dir { "[ / ] " } else { "[ # ] " };
     let name = entry.path.display().to_string();
@@ -48,18 +197,253 @@ pub fn create_list_item<'a>(entry: &'a FileEntry, no_permissions: bool, no_modif
         display
     };
     
-    if entry.is_dir {
+    if entry.is_dir || !highlight.is_empty() {
         let name_start = display.find(&name).unwrap_or(0);
         let name_end = name_start + name.len();
-        
-        let spans = vec![
-            Span::raw(display[..name_start].to_string()),
-            Span::styled(display[name_start..name_end].to_string(), Style::default().fg(Color::LightBlue)),
-            Span::raw(display[name_end..].to_string())
-        ];
+        let style = if entry.is_dir { Style::default().fg(theme().primary) } else { Style::default() };
+
+        let mut spans = vec![Span::raw(display[..name_start].to_string())];
+        spans.extend(highlighted_spans(&display[name_start..name_end], highlight, style));
+        spans.push(Span::raw(display[name_end..].to_string()));
+        if let Some(tool) = &entry.artifact {
+            spans.push(Span::styled(format!(" [{} artifact]", tool), Style::default().fg(theme().accent)));
+        }
         
         ListItem::new(Line::from(spans))
     } else {
         ListItem::new(display)
     }
-} 
\ No newline at end of file
+}
+
+/// Splits `text` into spans, emphasising the chars at `positions`, which
+/// are in ascending order.
+pub fn highlighted_spans(text: &str, positions: &[usize], style: Style) -> Vec<Span<'static>> {
+    let emphasis = style.fg(theme().accent).add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans: Vec<Span> = Vec::new();
+    let mut current = String::new();
+    let mut current_highlighted = false;
+    let mut positions = positions.iter().peekable();
+
+    for (i, c) in text.chars().enumerate() {
+        let highlighted = positions.next_if(|&&p| p <= i).is_some_and(|&p| p == i);
+        if highlighted != current_highlighted && !current.is_empty() {
+            let style = if current_highlighted { emphasis } else { style };
+            spans.push(Span::styled(std::mem::take(&mut current), style));
+        }
+        current_highlighted = highlighted;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        spans.push(Span::styled(current, if current_highlighted { emphasis } else { style }));
+    }
+    spans
+}
+
+pub fn create_extension_item(stats: &ExtensionStats, total: u64) -> ListItem<'static> {
+    ListItem::new(Line::from(extension_spans(stats, total)))
+}
+
+/// Lists `stats` with checkboxes; percentages are of `total`, since
+/// compound extensions overlap the simple ones. `case_key` is the key that
+/// toggles `ignore_case`, if any.
+pub fn create_extension_picker(
+    stats: &[ExtensionStats],
+    total: u64,
+    checked: &BTreeSet<String>,
+    ignore_case: bool,
+    case_key: Option<String>,
+) -> List<'static> {
+    let items: Vec<ListItem> = stats
+        .iter()
+        .map(|s| {
+            let key = if ignore_case { s.extension.to_lowercase() } else { s.extension.clone() };
+            let checkbox = if checked.contains(&key) { "[x] " } else { "[ ] " };
+            let mut spans = vec![Span::raw(checkbox)];
+            spans.extend(extension_spans(s, total));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let case = case_key
+        .map(|key| format!("{}: Ignore case ({}) | ", key, if ignore_case { "on" } else { "off" }))
+        .unwrap_or_default();
+    let hint = format!(" Space: Toggle | {}Enter: Apply | Esc: Cancel ", case);
+
+    List::new(items)
+        .block(
+            Block::default()
+                .title("⯈ Filter by extension ⯇")
+                .title(Title::from(hint).position(Position::Bottom))
+                .borders(Borders::ALL),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+}
+
+fn extension_spans(stats: &ExtensionStats, total: u64) -> Vec<Span<'static>> {
+    let percent = if total > 0 {
+        stats.size as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    vec![
+        Span::styled(format!("{:<12}", format!(".{}", stats.extension)), Style::default().fg(theme().primary)),
+        Span::raw(format!(
+            "{:>10}  {:>5.1}%  ({} files)",
+            crate::utils::format_size(stats.size),
+            percent,
+            stats.files
+        )),
+    ]
+}
+
+pub fn create_artifact_summary_item(summary: &ArtifactSummary, total: u64) -> ListItem<'static> {
+    let percent = if total > 0 {
+        summary.size as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    let spans = vec![
+        Span::styled(format!("{:<12}", summary.tool), Style::default().fg(theme().accent)),
+        Span::raw(format!(
+            "{:>10}  {:>5.1}%  ({} directories)",
+            crate::utils::format_size(summary.size),
+            percent,
+            summary.dirs
+        )),
+    ];
+
+    ListItem::new(Line::from(spans))
+}
+
+pub fn create_project_item(project: &Project) -> ListItem<'static> {
+    let spans = vec![
+        Span::styled(project.path.display().to_string(), Style::default().fg(theme().primary)),
+        Span::raw(format!(
+            " ({}, {} artifacts) [untouched for {}]",
+            crate::utils::format_size(project.size),
+            crate::utils::format_size(project.artifact_size),
+            crate::utils::format_duration(project.untouched_for())
+        )),
+    ];
+
+    ListItem::new(Line::from(spans))
+}
+
+pub fn create_trash_item(item: &TrashItem) -> ListItem<'static> {
+    let spans = vec![
+        Span::styled(item.original_path.display().to_string(), Style::default().fg(theme().primary)),
+        Span::raw(format!(
+            " ({}) [deleted {}]",
+            crate::utils::format_size(item.size),
+            item.deleted_at
+        )),
+    ];
+
+    ListItem::new(Line::from(spans))
+}
+
+pub fn create_preview(title: String, preview: Option<&Preview>) -> Paragraph<'static> {
+    let dim = Style::default().fg(theme().muted);
+    let lines: Vec<Line> = match preview {
+        None => vec![Line::styled("Loading…", dim)],
+        Some(Preview::Text(lines)) => lines.iter().map(|l| Line::from(l.clone())).collect(),
+        Some(Preview::Hex(lines)) => lines.iter().map(|l| Line::styled(l.clone(), Style::default().fg(theme().muted))).collect(),
+        Some(Preview::Archive { members, truncated }) => {
+            let summary = if *truncated {
+                format!("First {} members", members.len())
+            } else {
+                format!("{} members", members.len())
+            };
+            std::iter::once(Line::styled(summary, dim))
+                .chain(members.iter().map(|(name, size)| {
+                    Line::from(format!("{:>10}  {}", crate::utils::format_size(*size), name))
+                }))
+                .collect()
+        }
+        Some(Preview::Directory { children, total, truncated }) => {
+            let summary = if *truncated {
+                format!("At least {} in total", crate::utils::format_size(*total))
+            } else {
+                format!("{} in total", crate::utils::format_size(*total))
+            };
+            std::iter::once(Line::styled(summary, dim))
+                .chain(children.iter().map(|(path, size, is_dir)| {
+                    let percent = if *total > 0 { *size as f64 / *total as f64 * 100.0 } else { 0.0 };
+                    let name = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+                    let style = if *is_dir { Style::default().fg(theme().primary) } else { Style::default() };
+                    Line::from(vec![
+                        Span::raw(format!("{:>10} {:>5.1}%  ", crate::utils::format_size(*size), percent)),
+                        Span::styled(name, style),
+                    ])
+                }))
+                .collect()
+        }
+        Some(Preview::Message(message)) => {
+            vec![Line::styled(message.clone(), dim.add_modifier(Modifier::ITALIC))]
+        }
+    };
+
+    Paragraph::new(lines).block(Block::default().title(format!("⯈ {} ⯇", title)).borders(Borders::ALL))
+}
+
+pub fn create_info_panel(info: &Result<EntryInfo, String>) -> Paragraph<'static> {
+    let block = Block::default().title("⯈ Info ⯇").borders(Borders::ALL);
+    let info = match info {
+        Ok(info) => info,
+        Err(e) => {
+            return Paragraph::new(Line::styled(e.clone(), Style::default().fg(theme().muted))).block(block);
+        }
+    };
+
+    let name = |name: &Option<String>, id: u32| match name {
+        Some(name) => format!("{} ({})", name, id),
+        None => id.to_string(),
+    };
+    let time = |time: &std::time::SystemTime| {
+        chrono::DateTime::<chrono::Local>::from(*time)
+            .format("%Y-%m-%d %H:%M:%S%.3f %z")
+            .to_string()
+    };
+
+    let mut rows = vec![
+        ("Path", info.path.display().to_string()),
+        ("Type", info.file_type.to_string()),
+    ];
+    if let Some(target) = &info.symlink_target {
+        rows.push(("Target", target.display().to_string()));
+    }
+    rows.extend([
+        ("Inode", info.inode.to_string()),
+        ("Device", format!("{}:{}", info.device.0, info.device.1)),
+        ("Links", info.links.to_string()),
+        ("Owner", name(&info.owner, info.uid)),
+        ("Group", name(&info.group, info.gid)),
+        ("Mode", format!("{} ({})", info.octal_mode(), info.symbolic_mode())),
+        ("Accessed", time(&info.accessed)),
+        ("Modified", time(&info.modified)),
+        ("Changed", time(&info.changed)),
+        ("Born", info.created.as_ref().map_or_else(|| "unknown".to_string(), time)),
+        (
+            "Size",
+            format!("{} ({} bytes)", crate::utils::format_size(info.apparent_size), info.apparent_size),
+        ),
+        (
+            "Allocated",
+            format!("{} ({} bytes)", crate::utils::format_size(info.allocated_size), info.allocated_size),
+        ),
+        ("Block size", info.block_size.to_string()),
+    ]);
+
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!("{:<11}", label), Style::default().fg(theme().primary)),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+    Paragraph::new(lines).wrap(Wrap { trim: false }).block(block)
+}
@@ -25,4 +25,45 @@ pub fn format_duration(duration: Duration) -> String {
     } else {
         format!("{}d", secs / 86400)
     }
-} 
\ No newline at end of file
+}
+
+/// Parses a size such as `500K`, `100M` or `1.5G`, in the same binary units
+/// `format_size` prints.
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        "p" | "pb" | "pib" => 1 << 50,
+        _ => return Err(format!("Size must look like 500K, 100M or 1.5G, got: {}", text)),
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n >= 0.0 => Ok((n * multiplier as f64) as u64),
+        _ => Err(format!("Size must look like 500K, 100M or 1.5G, got: {}", text)),
+    }
+}
+
+/// Parses an age such as `90s`, `30m`, `12h`, `30d`, `2w` or `1y`.
+pub fn parse_age(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let seconds: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        "y" => 365 * 86400,
+        _ => return Err(format!("Age must look like 90s, 30m, 12h, 30d, 2w or 1y, got: {}", text)),
+    };
+    number
+        .parse::<u64>()
+        .map(|n| Duration::from_secs(n.saturating_mul(seconds)))
+        .map_err(|_| format!("Age must look like 90s, 30m, 12h, 30d, 2w or 1y, got: {}", text))
+}
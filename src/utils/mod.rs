@@ -1,3 +1,5 @@
 pub mod formatting;
+pub mod process;
 
-pub use formatting::*; 
\ No newline at end of file
+pub use formatting::*;
+pub use process::*;
@@ -0,0 +1,18 @@
+use std::env;
+use std::process::Command;
+
+/// Builds a command from the first of `vars` that is set and non-empty,
+/// splitting it on whitespace so values like `code -w` work, or from
+/// `fallback` when none is set.
+pub fn command_from_env(vars: &[&str], fallback: &str) -> Command {
+    let value = vars
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| fallback.to_string());
+
+    let mut words = value.split_whitespace();
+    let mut command = Command::new(words.next().unwrap_or(fallback));
+    command.args(words);
+    command
+}